@@ -0,0 +1,132 @@
+/// Repeated timing of solvers with summary statistics
+use std::time::Instant;
+
+/// Number of runs to discard before timing and the number of timed runs
+#[derive(Debug, Copy, Clone)]
+pub struct Bench {
+    pub warm_up: usize,
+    pub iterations: usize,
+}
+
+impl Bench {
+    /// Runs a function for the warm-up runs and then the timed runs, stopping early if the
+    /// function fails. Returns the last result along with the time taken for each timed run.
+    pub fn run<T, E>(&self, mut f: impl FnMut() -> Result<T, E>) -> (Result<T, E>, Vec<u128>) {
+        for _ in 0..self.warm_up {
+            if let Err(err) = f() {
+                return (Err(err), Vec::new());
+            }
+        }
+        let mut samples = Vec::with_capacity(self.iterations);
+        loop {
+            let now = Instant::now();
+            let result = f();
+            samples.push(now.elapsed().as_nanos());
+            if result.is_err() || samples.len() >= self.iterations {
+                break (result, samples);
+            }
+        }
+    }
+}
+
+/// Summary of a set of timings, all in nanoseconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: u128,
+    pub max: u128,
+    pub median: u128,
+    pub mean: u128,
+    pub std_dev: u128,
+    pub low_outliers: usize,
+    pub high_outliers: usize,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[u128]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let runs = sorted.len();
+
+        let median = if runs.is_multiple_of(2) {
+            (sorted[runs / 2 - 1] + sorted[runs / 2]) / 2
+        } else {
+            sorted[runs / 2]
+        };
+        let mean = sorted.iter().sum::<u128>() as f64 / runs as f64;
+        let variance = sorted
+            .iter()
+            .map(|&s| (s as f64 - mean).powi(2))
+            .sum::<f64>()
+            / (runs - 1).max(1) as f64;
+
+        // Tukey's fences: anything further than 1.5 times the interquartile range from the
+        // lower or upper quartile counts as an outlier
+        let (low_outliers, high_outliers) = if runs >= 4 {
+            let q1 = sorted[runs / 4] as f64;
+            let q3 = sorted[3 * runs / 4] as f64;
+            let iqr = q3 - q1;
+            let low = sorted
+                .iter()
+                .filter(|&&s| (s as f64) < q1 - 1.5 * iqr)
+                .count();
+            let high = sorted
+                .iter()
+                .filter(|&&s| (s as f64) > q3 + 1.5 * iqr)
+                .count();
+            (low, high)
+        } else {
+            (0, 0)
+        };
+
+        Some(Self {
+            runs,
+            min: sorted[0],
+            max: sorted[runs - 1],
+            median,
+            mean: mean.round() as u128,
+            std_dev: variance.sqrt().round() as u128,
+            low_outliers,
+            high_outliers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::from_samples(&[10, 12, 11, 13, 100, 12, 11, 1]).unwrap();
+        assert_eq!(stats.runs, 8);
+        assert_eq!(stats.min, 1);
+        assert_eq!(stats.max, 100);
+        assert_eq!(stats.median, 11);
+        assert_eq!(stats.mean, 21);
+        assert_eq!(stats.low_outliers, 1);
+        assert_eq!(stats.high_outliers, 1);
+    }
+
+    #[test]
+    fn test_bench_stops_on_error() {
+        let bench = Bench {
+            warm_up: 1,
+            iterations: 5,
+        };
+        let mut count = 0;
+        let (result, samples) = bench.run(|| {
+            count += 1;
+            if count < 3 {
+                Ok(count)
+            } else {
+                Err(count)
+            }
+        });
+        assert_eq!(result, Err(3));
+        assert_eq!(samples.len(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+/// Cooperative cancellation of long-running solvers
+///
+/// A deadline is set for the current thread while a solver runs. Long-running loops call
+/// `check` or `is_cancelled` to find out whether they should stop early.
+use std::cell::Cell;
+use std::time::Instant;
+
+use crate::SolveError;
+
+/// Number of calls between reading the clock, to keep checks cheap in tight loops
+const CHECK_INTERVAL: u32 = 1024;
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+    static CANCELLED: Cell<bool> = const { Cell::new(false) };
+    static TICKS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Runs a function with a deadline set for the current thread, restoring the previous deadline
+/// afterwards
+pub fn with_deadline<T>(deadline: Instant, f: impl FnOnce() -> T) -> T {
+    let previous = DEADLINE.with(|d| d.replace(Some(deadline)));
+    let was_cancelled = CANCELLED.with(|c| c.replace(false));
+    let result = f();
+    DEADLINE.with(|d| d.set(previous));
+    CANCELLED.with(|c| c.set(was_cancelled));
+    result
+}
+
+/// Deadline set for the current thread, for passing on to any threads it starts
+pub fn deadline() -> Option<Instant> {
+    DEADLINE.with(Cell::get)
+}
+
+/// Whether the deadline for the current thread has passed. The clock is only read every so often,
+/// so this may return `false` for a short while after the deadline.
+pub fn is_cancelled() -> bool {
+    if CANCELLED.with(Cell::get) {
+        return true;
+    }
+    let ticks = TICKS.with(|t| {
+        let ticks = t.get().wrapping_add(1);
+        t.set(ticks);
+        ticks
+    });
+    if !ticks.is_multiple_of(CHECK_INTERVAL) {
+        return false;
+    }
+    let expired = DEADLINE.with(|d| d.get().is_some_and(|d| Instant::now() >= d));
+    if expired {
+        CANCELLED.with(|c| c.set(true));
+    }
+    expired
+}
+
+/// Whether the deadline for the current thread has passed, reading the clock every time
+pub fn has_expired() -> bool {
+    let expired = CANCELLED.with(Cell::get)
+        || DEADLINE.with(|d| d.get().is_some_and(|d| Instant::now() >= d));
+    CANCELLED.with(|c| c.set(expired));
+    expired
+}
+
+/// Returns an error if the deadline for the current thread has passed
+pub fn check() -> Result<(), SolveError> {
+    if is_cancelled() {
+        Err(SolveError::timed_out())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_deadline() {
+        assert!(!has_expired());
+        let passed = Instant::now() - Duration::from_millis(1);
+        let result = with_deadline(passed, || (0..CHECK_INTERVAL).find_map(|_| check().err()));
+        assert!(result.is_some_and(|e| e.is_timed_out()));
+        assert!(!has_expired());
+
+        let future = Instant::now() + Duration::from_secs(60);
+        assert!(with_deadline(future, || (0..CHECK_INTERVAL).all(|_| check().is_ok())));
+        assert_eq!(with_deadline(future, deadline), Some(future));
+        assert_eq!(deadline(), None);
+    }
+}
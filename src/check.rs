@@ -0,0 +1,220 @@
+/// Known answers for each day and part, used to check solvers haven't changed their results
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::{read_to_string, write};
+use std::io::{Error as IoError, ErrorKind};
+use std::path::Path;
+
+use toml::value::{Table, Value};
+
+#[derive(Debug)]
+pub enum AnswersError {
+    Io(IoError),
+    Parse(toml::de::Error),
+    Format(String),
+}
+
+impl Display for AnswersError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            AnswersError::Io(err) => write!(f, "{}", err),
+            AnswersError::Parse(err) => write!(f, "{}", err),
+            AnswersError::Format(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for AnswersError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AnswersError::Io(err) => Some(err),
+            AnswersError::Parse(err) => Some(err),
+            AnswersError::Format(_) => None,
+        }
+    }
+}
+
+impl From<IoError> for AnswersError {
+    fn from(err: IoError) -> Self {
+        AnswersError::Io(err)
+    }
+}
+
+/// Result of comparing an answer with the expected one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail(String),
+    Missing,
+}
+
+/// Expected answers keyed by day and part. Stored as TOML with one table per day:
+///
+/// ```toml
+/// [d01]
+/// part1 = "3429947"
+/// part2 = "5142043"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers(BTreeMap<(usize, usize), String>);
+
+impl Answers {
+    fn day_key(day: usize) -> String {
+        format!("d{:02}", day)
+    }
+
+    fn part_key(part: usize) -> String {
+        format!("part{}", part)
+    }
+
+    pub fn parse(s: &str) -> Result<Self, AnswersError> {
+        let value = s.parse::<Value>().map_err(AnswersError::Parse)?;
+        let mut answers = BTreeMap::new();
+        for (day_key, parts) in value.as_table().into_iter().flatten() {
+            let day = day_key
+                .strip_prefix('d')
+                .and_then(|d| d.parse::<usize>().ok())
+                .ok_or_else(|| AnswersError::Format(format!("invalid day {:?}", day_key)))?;
+            let parts = parts.as_table().ok_or_else(|| {
+                AnswersError::Format(format!("expected a table of parts for {:?}", day_key))
+            })?;
+            for (part_key, answer) in parts.iter() {
+                let part = part_key
+                    .strip_prefix("part")
+                    .and_then(|p| p.parse::<usize>().ok())
+                    .ok_or_else(|| {
+                        AnswersError::Format(format!(
+                            "invalid part {:?} in {:?}",
+                            part_key, day_key
+                        ))
+                    })?;
+                let answer = match answer {
+                    Value::String(s) => s.clone(),
+                    Value::Integer(i) => i.to_string(),
+                    _ => Err(AnswersError::Format(format!(
+                        "answer for {}.{} must be a string or integer",
+                        day_key, part_key
+                    )))?,
+                };
+                answers.insert((day, part), answer);
+            }
+        }
+        Ok(Self(answers))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AnswersError> {
+        Self::parse(&read_to_string(path)?)
+    }
+
+    /// Loads answers from a file if it exists, or starts with none
+    pub fn load_or_default(path: &Path) -> Result<Self, AnswersError> {
+        match read_to_string(path) {
+            Ok(s) => Self::parse(&s),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut days = Table::new();
+        for (&(day, part), answer) in self.0.iter() {
+            let parts = days
+                .entry(Self::day_key(day))
+                .or_insert_with(|| Value::Table(Table::new()));
+            if let Value::Table(t) = parts {
+                t.insert(Self::part_key(part), Value::String(answer.clone()));
+            }
+        }
+        toml::to_string_pretty(&Value::Table(days)).expect("answers can always be serialised")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), AnswersError> {
+        Ok(write(path, self.to_toml())?)
+    }
+
+    pub fn insert(&mut self, day: usize, part: usize, answer: &str) {
+        self.0.insert((day, part), answer.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn check(&self, day: usize, part: usize, answer: &str) -> Verdict {
+        match self.0.get(&(day, part)) {
+            Some(expected) if normalise(expected) == normalise(answer) => Verdict::Pass,
+            Some(expected) => Verdict::Fail(expected.clone()),
+            None => Verdict::Missing,
+        }
+    }
+}
+
+/// Ignores trailing whitespace on each line so rendered answers survive editors
+fn normalise(s: &str) -> Vec<&str> {
+    s.trim_end().lines().map(|l| l.trim_end()).collect()
+}
+
+/// Compares expected and actual answers line by line, marking lines that differ with `-` for
+/// expected and `+` for actual
+pub fn diff(expected: &str, actual: &str) -> Vec<String> {
+    let expected = normalise(expected);
+    let actual = normalise(actual);
+    let mut lines = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => lines.push(format!("  {}", e)),
+            (e, a) => {
+                if let Some(e) = e {
+                    lines.push(format!("- {}", e));
+                }
+                if let Some(a) = a {
+                    lines.push(format!("+ {}", a));
+                }
+            }
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut answers = Answers::default();
+        answers.insert(1, 1, "3429947");
+        answers.insert(8, 2, "# #  \n #  ");
+        answers.insert(25, 1, "8462464");
+        let parsed = Answers::parse(&answers.to_toml()).unwrap();
+        assert_eq!(parsed, answers);
+        assert_eq!(parsed.check(1, 1, "3429947"), Verdict::Pass);
+        assert_eq!(parsed.check(8, 2, "# #\n #"), Verdict::Pass);
+        assert_eq!(parsed.check(1, 2, "0"), Verdict::Missing);
+        assert_eq!(
+            parsed.check(25, 1, "0"),
+            Verdict::Fail("8462464".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Answers::parse("[d01]\npart1 = 3429947").is_ok());
+        assert!(Answers::parse("[day1]\npart1 = \"1\"").is_err());
+        assert!(Answers::parse("[d01]\na = \"1\"").is_err());
+        assert!(Answers::parse("[d01]\npart1 = [1]").is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            diff("# #\n###\n# #", "# #\n# #"),
+            vec!["  # #", "- ###", "+ # #", "- # #"]
+        );
+    }
+}
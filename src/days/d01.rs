@@ -1,11 +1,10 @@
 /// Advent of Code 2019, day 1
 /// https://adventofcode.com/2019/day/1
+use super::{Context, SolveError};
 
-fn parse_input() -> Vec<i32> {
-    let data = include_bytes!("input/d01.txt");
-    String::from_utf8_lossy(data)
-        .split('\n')
-        .filter_map(|s| s.parse::<i32>().ok())
+pub fn parse(data: &str) -> Result<Vec<i32>, SolveError> {
+    data.split('\n')
+        .map(|s| s.parse::<i32>().context(format!("invalid mass {:?}", s)))
         .collect()
 }
 
@@ -13,16 +12,16 @@ fn fuel_required(mass: i32) -> i32 {
     mass / 3 - 2
 }
 
-pub fn part_a() -> String {
-    parse_input()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    Ok(parse(data)?
         .iter()
         .map(|&mass| fuel_required(mass))
         .sum::<i32>()
-        .to_string()
+        .to_string())
 }
 
-pub fn part_b() -> String {
-    parse_input()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    Ok(parse(data)?
         .iter()
         .map(|&mass| {
             let mut total = 0;
@@ -34,5 +33,5 @@ pub fn part_b() -> String {
             total
         })
         .sum::<i32>()
-        .to_string()
+        .to_string())
 }
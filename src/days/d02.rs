@@ -1,53 +1,37 @@
 /// Advent of Code 2019, day 2
 /// https://adventofcode.com/2019/day/2
+use super::{Context, SolveError};
+use crate::intcode::search::{find_inputs, output, SearchError};
+use crate::intcode::{Intcode, RunState};
 
-fn parse_input() -> Vec<usize> {
-    let data = include_bytes!("input/d02.txt");
-    String::from_utf8_lossy(data)
-        .split(',')
-        .map(|i| i.parse::<usize>().unwrap())
-        .collect()
+/// Output the program has to produce for part B
+const TARGET: i64 = 19690720;
+
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
-fn run_program(initial: Vec<usize>, noun: usize, verb: usize) -> Vec<usize> {
-    let mut state = initial.clone();
-    state[1] = noun;
-    state[2] = verb;
-    let mut cursor = 0;
-    loop {
-        match state[cursor] {
-            1 => {
-                // Adds values in 1st and 2nd indices and assign to 3rd index
-                let o = state[cursor + 3];
-                state[o] = state[state[cursor + 1]] + state[state[cursor + 2]];
-                cursor += 4;
-            }
-            2 => {
-                // Multiples values in 1st and 2nd indices and assign to 3rd index
-                let o = state[cursor + 3];
-                state[o] = state[state[cursor + 1]] * state[state[cursor + 2]];
-                cursor += 4;
-            }
-            99 => {
-                // Halts program
-                break;
-            }
-            x => panic!("Encountered invalid opcode: '{}'", x),
-        };
+fn search_error(err: SearchError) -> SolveError {
+    match err {
+        SearchError::Stopped {
+            state: RunState::Cancelled,
+            ..
+        } => SolveError::timed_out(),
+        err => SolveError::with_cause("Intcode program failed", err),
     }
-
-    state
 }
 
-pub fn part_a() -> String {
-    run_program(parse_input(), 12, 2)[0].to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let result = output(&parse(data)?, 12, 2).map_err(search_error)?;
+    Ok(result.to_string())
 }
 
-pub fn part_b() -> String {
-    let (noun, verb) = (0usize..100)
-        .flat_map(|j| (0usize..100).map(move |i| (i, j)))
-        .find(|(n, v)| run_program(parse_input(), *n, *v)[0] == 19690720)
-        .unwrap();
-
-    (100 * noun + verb).to_string()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let program = parse(data)?;
+    match find_inputs(&program, TARGET, 0..100, 0..100).map_err(search_error)? {
+        Some((noun, verb)) => Ok((100 * noun + verb).to_string()),
+        None => Err(SolveError::new(
+            "no noun and verb produce the expected output",
+        )),
+    }
 }
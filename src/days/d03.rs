@@ -2,33 +2,37 @@
 /// https://adventofcode.com/2019/day/3
 use std::collections::HashMap;
 
+use super::{Context, SolveError};
+
 type Coord = (i32, i32);
 
 #[derive(Debug)]
-struct Move {
+pub struct Move {
     x: i32,
     y: i32,
     d: i32,
 }
 
 impl Move {
-    fn from_str(cmd: &str) -> Move {
+    fn from_str(cmd: &str) -> Result<Move, SolveError> {
         let mut dx: i32 = 0;
         let mut dy: i32 = 0;
-        match cmd.chars().nth(0).unwrap() {
+        match cmd.chars().next().context("move command is empty")? {
             'D' => dy = -1,
             'L' => dx = -1,
             'R' => dx = 1,
             'U' => dy = 1,
-            x => panic!("Move command '{}' is invalid.", x),
+            x => return Err(SolveError::new(format!("move command '{}' is invalid", x))),
         }
-        let dist = cmd[1..].parse::<i32>().unwrap();
+        let dist = cmd[1..]
+            .parse::<i32>()
+            .context(format!("invalid distance in move {:?}", cmd))?;
 
-        Move {
+        Ok(Move {
             x: dist * dx,
             y: dist * dy,
             d: dist,
-        }
+        })
     }
 
     fn x(&self) -> i32 {
@@ -118,36 +122,26 @@ impl Grid {
     }
 }
 
-fn parse_input() -> Vec<Vec<Move>> {
-    let data = include_bytes!("input/d03.txt");
-    String::from_utf8_lossy(data)
-        .split('\n')
-        .map(|s| s.split(',').map(|c| Move::from_str(c)).collect())
+pub fn parse(data: &str) -> Result<Vec<Vec<Move>>, SolveError> {
+    data.split('\n')
+        .map(|s| s.split(',').map(Move::from_str).collect())
         .collect()
 }
 
-pub fn part_a() -> String {
+pub fn part_a(data: &str) -> Result<String, SolveError> {
     let mut g = Grid::new();
-    for w in parse_input().iter() {
+    for w in parse(data)?.iter() {
         g.add_wire(w);
     }
-    g.intersections()
-        .iter()
-        .map(|&p| p.distance())
-        .min()
-        .unwrap()
-        .to_string()
+    let min = g.intersections().iter().map(|&p| p.distance()).min();
+    Ok(min.context("wires do not intersect")?.to_string())
 }
 
-pub fn part_b() -> String {
+pub fn part_b(data: &str) -> Result<String, SolveError> {
     let mut g = Grid::new();
-    for w in parse_input().iter() {
+    for w in parse(data)?.iter() {
         g.add_wire(w);
     }
-    g.intersections()
-        .iter()
-        .map(|&p| p.length())
-        .min()
-        .unwrap()
-        .to_string()
+    let min = g.intersections().iter().map(|&p| p.length()).min();
+    Ok(min.context("wires do not intersect")?.to_string())
 }
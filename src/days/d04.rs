@@ -1,8 +1,6 @@
 /// Advent of Code 2019, day 4
 /// https://adventofcode.com/2019/day/4
-
-const MIN: u32 = 357253;
-const MAX: u32 = 892942;
+use super::{Context, SolveError};
 
 struct IterDigits {
     remaining: u32,
@@ -33,17 +31,29 @@ fn iter_digits(integer: u32) -> IterDigits {
     }
 }
 
-pub fn part_a() -> String {
-    (MIN..=MAX)
+pub fn parse(data: &str) -> Result<(u32, u32), SolveError> {
+    let mut bounds = data.trim().split('-').map(|s| s.parse::<u32>());
+    match (bounds.next(), bounds.next(), bounds.next()) {
+        (Some(min), Some(max), None) => Ok((
+            min.context("invalid lower bound")?,
+            max.context("invalid upper bound")?,
+        )),
+        _ => Err(SolveError::new("range must be in form 'min-max'")),
+    }
+}
+
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let (min, max) = parse(data)?;
+    let count = (min..=max)
         .filter(|&pw| {
             let mut p: Option<u8> = None;
             let mut adj = false;
             for d in iter_digits(pw) {
-                if p.is_some() {
-                    if p.unwrap() < d {
+                if let Some(q) = p {
+                    if q < d {
                         return false;
                     }
-                    if p.unwrap() == d {
+                    if q == d {
                         adj = true;
                     }
                 }
@@ -51,21 +61,22 @@ pub fn part_a() -> String {
             }
             adj
         })
-        .count()
-        .to_string()
+        .count();
+    Ok(count.to_string())
 }
 
-pub fn part_b() -> String {
-    (MIN..=MAX)
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let (min, max) = parse(data)?;
+    let count = (min..=max)
         .filter(|&pw| {
             let mut p: Option<u8> = None;
             let mut pairs: Vec<u8> = Vec::new();
             for d in iter_digits(pw) {
-                if p.is_some() {
-                    if p.unwrap() < d {
+                if let Some(q) = p {
+                    if q < d {
                         return false;
                     }
-                    if p.unwrap() == d {
+                    if q == d {
                         pairs.push(d);
                     }
                 }
@@ -76,6 +87,6 @@ pub fn part_b() -> String {
                 .map(|&p| pairs.iter().filter(|&&q| p == q).count())
                 .any(|c| c == 1)
         })
-        .count()
-        .to_string()
+        .count();
+    Ok(count.to_string())
 }
@@ -1,26 +1,24 @@
 /// Advent of Code 2019, day 5
 /// https://adventofcode.com/2019/day/5
 use std::iter::once;
-use std::str::from_utf8;
 
-use super::intcode::Intcode;
+use super::{Context, SolveError};
+use crate::intcode::Intcode;
 
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d05.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
-fn run_intcode(value: i64) -> i64 {
-    let mut program = setup_intcode();
-    program.run(once(value));
-    let output = program.collect::<Vec<i64>>();
-    output[output.len() - 1]
+fn run_intcode(data: &str, value: i64) -> Result<i64, SolveError> {
+    let mut program = parse(data)?;
+    program.run(once(value)).context("Intcode program failed")?;
+    program.last().context("program produced no output")
 }
 
-pub fn part_a() -> String {
-    run_intcode(1).to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    Ok(run_intcode(data, 1)?.to_string())
 }
 
-pub fn part_b() -> String {
-    run_intcode(5).to_string()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    Ok(run_intcode(data, 5)?.to_string())
 }
@@ -4,8 +4,10 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 use std::iter::{empty, once, FromIterator};
 
+use super::{Context, SolveError};
+
 #[derive(Debug)]
-struct Graph<T>
+pub struct Graph<T>
 where
     T: Eq + Hash,
 {
@@ -126,12 +128,16 @@ where
     }
 }
 
-fn parse_graph() -> Graph<String> {
-    let data = include_bytes!("input/d06.txt");
-    Graph::from_iter(String::from_utf8_lossy(data).split('\n').map(|l| {
-        let mut i = l.split(')').map(|s| s.to_string());
-        (i.next().unwrap(), i.next().unwrap())
-    }))
+pub fn parse(data: &str) -> Result<Graph<String>, SolveError> {
+    data.split('\n')
+        .map(|l| {
+            let mut i = l.split(')').map(|s| s.to_string());
+            match (i.next(), i.next(), i.next()) {
+                (Some(a), Some(b), None) => Ok((a, b)),
+                _ => Err(SolveError::new(format!("invalid orbit {:?}", l))),
+            }
+        })
+        .collect()
 }
 
 fn count_edges(g: &Graph<String>, start: &String) -> usize {
@@ -140,17 +146,20 @@ fn count_edges(g: &Graph<String>, start: &String) -> usize {
         .sum::<usize>()
 }
 
-pub fn part_a() -> String {
-    count_edges(&parse_graph(), &"COM".to_string()).to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    Ok(count_edges(&parse(data)?, &"COM".to_string()).to_string())
 }
 
-pub fn part_b() -> String {
-    let g = parse_graph();
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let g = parse(data)?;
     let y = "YOU".to_string();
     let s = "SAN".to_string();
-    let yp = g.parent(&y).unwrap();
-    let sp = g.parent(&s).unwrap();
-    g.distance(yp, sp).unwrap().to_string()
+    let yp = g.parent(&y).context("YOU is not orbiting anything")?;
+    let sp = g.parent(&s).context("SAN is not orbiting anything")?;
+    let distance = g
+        .distance(yp, sp)
+        .context("YOU and SAN are not connected")?;
+    Ok(distance.to_string())
 }
 
 #[cfg(test)]
@@ -159,7 +168,7 @@ mod test {
 
     fn get_test_graph() -> Graph<String> {
         Graph::from_iter(
-            vec![
+            [
                 ("COM", "B"),
                 ("B", "C"),
                 ("C", "D"),
@@ -2,35 +2,51 @@
 /// https://adventofcode.com/2019/day/8
 use itertools::Itertools;
 
+use super::{Context, SolveError};
+
 const WIDTH: usize = 25;
 const HEIGHT: usize = 6;
 
-fn byte_to_int(i: u8) -> u8 {
-    if i >= 48 && i < 59 {
-        i - 48
+fn byte_to_int(i: u8) -> Result<u8, SolveError> {
+    if (48..59).contains(&i) {
+        Ok(i - 48)
     } else {
-        panic!("Byte {:#x} is not an ASCII integer.", i);
+        Err(SolveError::new(format!(
+            "byte {:#x} is not an ASCII integer",
+            i
+        )))
     }
 }
 
-fn read_input() -> Vec<u8> {
-    let data = include_bytes!("input/d08.txt");
-    data.iter().map(|&i| byte_to_int(i)).collect()
+pub fn parse(data: &str) -> Result<Vec<u8>, SolveError> {
+    let pixels = data
+        .bytes()
+        .map(byte_to_int)
+        .collect::<Result<Vec<u8>, _>>()?;
+    if pixels.is_empty() || pixels.len() % (WIDTH * HEIGHT) != 0 {
+        return Err(SolveError::new(format!(
+            "image of {} pixels cannot be split into {}x{} layers",
+            pixels.len(),
+            WIDTH,
+            HEIGHT
+        )));
+    }
+    Ok(pixels)
 }
 
-pub fn part_a() -> String {
-    let layer = read_input()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let layer = parse(data)?
         .chunks(WIDTH * HEIGHT)
         .min_by_key(|layer| layer.iter().filter(|&&i| i == 0).count())
-        .unwrap()
+        .context("image has no layers")?
         .to_vec();
     let ones = layer.iter().filter(|&&i| i == 1).count();
     let twos = layer.iter().filter(|&&i| i == 2).count();
-    (ones * twos).to_string()
+    Ok((ones * twos).to_string())
 }
 
-pub fn part_b() -> String {
-    let layers: Vec<Vec<u8>> = read_input()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let layers: Vec<Vec<u8>> = parse(data)?
         .chunks(WIDTH * HEIGHT)
         .map(|layer| layer.to_vec())
         .collect();
@@ -38,13 +54,12 @@ pub fn part_b() -> String {
         .map(|p| {
             (0..layers.len())
                 .map(|i| layers[i][p])
-                .skip_while(|&c| c == 2)
-                .nth(0)
-                .unwrap()
+                .find(|&c| c != 2)
+                .context(format!("pixel {} is transparent in every layer", p))
         })
-        .collect();
-    image
+        .collect::<Result<Vec<u8>, _>>()?;
+    Ok(image
         .chunks(WIDTH)
         .map(|l| l.iter().map(|&p| if p == 1 { "#" } else { " " }).join(" "))
-        .join("\n")
+        .join("\n"))
 }
@@ -1,25 +1,29 @@
 /// Advent of Code 2019, day 9
 /// https://adventofcode.com/2019/day/9
 use std::iter::once;
-use std::str::from_utf8;
 
-use super::intcode::Intcode;
+use super::{Context, SolveError};
+use crate::intcode::Intcode;
 
-fn get_data<'a>() -> &'a str {
-    let data = include_bytes!("input/d09.txt");
-    from_utf8(data).unwrap()
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
-pub fn part_a() -> String {
-    let mut program = get_data().parse::<Intcode>().unwrap();
-    program.run(once(1));
-    program.nth(0).unwrap().to_string()
+fn run_boost(data: &str, mode: i64) -> Result<String, SolveError> {
+    let mut program = parse(data)?;
+    program.run(once(mode)).context("Intcode program failed")?;
+    Ok(program
+        .next()
+        .context("program produced no output")?
+        .to_string())
 }
 
-pub fn part_b() -> String {
-    let mut program = get_data().parse::<Intcode>().unwrap();
-    program.run(once(2));
-    program.nth(0).unwrap().to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    run_boost(data, 1)
+}
+
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    run_boost(data, 2)
 }
 
 #[cfg(test)]
@@ -38,7 +42,7 @@ mod test {
     fn test_program_1() {
         let data = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
         let mut program = data.parse::<Intcode>().unwrap();
-        program.run(empty());
+        program.run(empty()).unwrap();
 
         assert_eq!(program.collect::<Vec<i64>>(), parse(data));
     }
@@ -47,8 +51,8 @@ mod test {
     fn test_program_2() {
         let data = "1102,34915192,34915192,7,4,7,99,0";
         let mut program = data.parse::<Intcode>().unwrap();
-        program.run(empty());
-        let value = program.nth(0).unwrap();
+        program.run(empty()).unwrap();
+        let value = program.next().unwrap();
 
         assert!(value >= 10i64.pow(15) && value < 10i64.pow(16));
     }
@@ -57,8 +61,8 @@ mod test {
     fn test_program_3() {
         let data = "104,1125899906842624,99";
         let mut program = data.parse::<Intcode>().unwrap();
-        program.run(empty());
+        program.run(empty()).unwrap();
 
-        assert_eq!(program.nth(0).unwrap(), parse(data)[1]);
+        assert_eq!(program.next().unwrap(), parse(data)[1]);
     }
 }
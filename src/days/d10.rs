@@ -2,6 +2,8 @@
 /// https://adventofcode.com/2019/day/10
 use std::f64::consts::PI;
 
+use super::{Context, SolveError};
+
 fn gcd(a: u32, b: u32) -> u32 {
     if b > a {
         gcd(b, a)
@@ -20,7 +22,7 @@ trait Vector {
 
 impl Vector for (i32, i32) {
     fn reduce(&self) -> Self {
-        let c = gcd(self.0.abs() as u32, self.1.abs() as u32) as i32;
+        let c = gcd(self.0.unsigned_abs(), self.1.unsigned_abs()) as i32;
         (self.0 / c, self.1 / c)
     }
 
@@ -38,14 +40,14 @@ impl Vector for (i32, i32) {
     }
 }
 
-struct LOS {
+struct Los {
     position: (usize, usize),
     map: Box<dyn Iterator<Item = (usize, usize)>>,
 }
 
-impl LOS {
-    fn new(position: (usize, usize), width: usize, height: usize) -> LOS {
-        LOS {
+impl Los {
+    fn new(position: (usize, usize), width: usize, height: usize) -> Los {
+        Los {
             position,
             map: Box::new((0..height).flat_map(move |j| (0..width).map(move |i| (i, j)))),
         }
@@ -62,7 +64,7 @@ impl LOS {
     }
 }
 
-impl Iterator for LOS {
+impl Iterator for Los {
     type Item = (i32, i32);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -76,7 +78,7 @@ impl Iterator for LOS {
 }
 
 fn los_clockwise(position: (usize, usize), width: usize, height: usize) -> Vec<(i32, i32)> {
-    let mut vectors: Vec<(i32, i32)> = LOS::new(position, height, width).collect();
+    let mut vectors: Vec<(i32, i32)> = Los::new(position, height, width).collect();
     vectors.sort_by(|&u, &v| u.angle().partial_cmp(&v.angle()).unwrap());
     vectors
 }
@@ -91,7 +93,7 @@ fn absolute_position(position: (usize, usize), offset: (i32, i32)) -> Option<(us
     }
 }
 
-struct Map {
+pub struct Map {
     points: Vec<bool>,
     width: usize,
     height: usize,
@@ -161,7 +163,7 @@ impl Map {
     }
 
     fn count_in_sight(&self, position: (usize, usize)) -> usize {
-        LOS::new(position, self.height, self.width)
+        Los::new(position, self.height, self.width)
             .filter_map(|o| self.find_in_sight(position, o))
             .count()
     }
@@ -171,41 +173,54 @@ impl Map {
     }
 }
 
-fn find_best_los(map: &Map) -> ((usize, usize), usize) {
+fn find_best_los(map: &Map) -> Option<((usize, usize), usize)> {
     map.points()
         .iter()
         .map(|&p| (p, map.count_in_sight(p)))
         .max_by_key(|(_, c)| *c)
-        .unwrap()
 }
 
-fn parse_string(string: String) -> Map {
+fn parse_string(string: String) -> Result<Map, SolveError> {
     let lines: Vec<&str> = string.split('\n').collect();
-    let vec: Vec<bool> = lines
+    let width = lines[0].len();
+    if lines.iter().any(|l| l.len() != width) {
+        return Err(SolveError::new("not all lines have the same width"));
+    }
+    let vec = lines
         .iter()
         .flat_map(|l| {
             l.chars().map(|c| match c {
-                '#' => true,
-                '.' => false,
-                x => panic!("{} not valid; must be '#' or '.'.", x),
+                '#' => Ok(true),
+                '.' => Ok(false),
+                x => Err(SolveError::new(format!(
+                    "{:?} not valid; must be '#' or '.'",
+                    x
+                ))),
             })
         })
-        .collect();
-    Map::new(vec, lines[0].len(), lines.len())
+        .collect::<Result<Vec<bool>, _>>()?;
+    Ok(Map::new(vec, width, lines.len()))
 }
 
-fn parse_input() -> Map {
-    let data = include_bytes!("input/d10.txt");
-    parse_string(String::from_utf8_lossy(data).to_string())
+pub fn parse(data: &str) -> Result<Map, SolveError> {
+    parse_string(data.to_string())
 }
 
-pub fn part_a() -> String {
-    find_best_los(&parse_input()).1.to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let (_, count) = find_best_los(&parse(data)?).context("map has no asteroids")?;
+    Ok(count.to_string())
 }
 
-pub fn part_b() -> String {
-    let mut map = parse_input();
-    let position: (usize, usize) = (31, 20);
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let target = 200;
+    let mut map = parse(data)?;
+    let (position, _) = find_best_los(&map).context("map has no asteroids")?;
+    if map.points().len() <= target {
+        return Err(SolveError::new(format!(
+            "fewer than {} asteroids to vaporise",
+            target
+        )));
+    }
     let vectors = los_clockwise(position, map.width(), map.height());
 
     let mut current: (usize, usize) = (0, 0);
@@ -216,11 +231,11 @@ pub fn part_b() -> String {
             current = p;
             count += 1;
         };
-        if count >= 200 {
+        if count >= target {
             break;
         }
     }
-    (current.0 * 100 + current.1).to_string()
+    Ok((current.0 * 100 + current.1).to_string())
 }
 
 #[cfg(test)]
@@ -235,7 +250,7 @@ mod test {
 
     #[test]
     fn test_los() {
-        let result: Vec<(i32, i32)> = LOS::new((0, 4), 5, 5).collect();
+        let result: Vec<(i32, i32)> = Los::new((0, 4), 5, 5).collect();
         assert_eq!(
             result,
             vec![
@@ -280,7 +295,7 @@ mod test {
 #.#.#
 .#.#.
 #.#.#";
-        let map = parse_string(input.to_string());
+        let map = parse_string(input.to_string()).unwrap();
         let pos: (usize, usize) = (0, 4);
         assert_eq!(map.count_in_sight(pos), 7);
     }
@@ -298,7 +313,7 @@ mod test {
 .##.#..###
 ##...#..#.
 .#....####";
-        let result = find_best_los(&parse_string(input.to_string()));
+        let result = find_best_los(&parse_string(input.to_string()).unwrap()).unwrap();
         assert_eq!(result.0, (5, 8));
         assert_eq!(result.1, 33);
     }
@@ -316,7 +331,7 @@ mod test {
 ..##....##
 ......#...
 .####.###.";
-        let result = find_best_los(&parse_string(input.to_string()));
+        let result = find_best_los(&parse_string(input.to_string()).unwrap()).unwrap();
         assert_eq!(result.0, (1, 2));
         assert_eq!(result.1, 35);
     }
@@ -334,7 +349,7 @@ mod test {
 #..#.#.###
 .##...##.#
 .....#.#..";
-        let result = find_best_los(&parse_string(input.to_string()));
+        let result = find_best_los(&parse_string(input.to_string()).unwrap()).unwrap();
         assert_eq!(result.0, (6, 3));
         assert_eq!(result.1, 41);
     }
@@ -362,7 +377,7 @@ mod test {
 .#.#.###########.###
 #.#.#.#####.####.###
 ###.##.####.##.#..##";
-        let result = find_best_los(&parse_string(input.to_string()));
+        let result = find_best_los(&parse_string(input.to_string()).unwrap()).unwrap();
         assert_eq!(result.0, (11, 13));
         assert_eq!(result.1, 210);
     }
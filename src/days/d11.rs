@@ -3,39 +3,40 @@
 use itertools::Itertools;
 use std::collections::HashSet;
 use std::iter::{empty, once};
-use std::str::from_utf8;
 
-use super::intcode::Intcode;
+use super::{Context, SolveError};
+use crate::cancel;
+use crate::intcode::{Intcode, RunState};
 
 type Square = (i32, i32);
 
 enum Direction {
-    DOWN,
-    LEFT,
-    RIGHT,
-    UP,
+    Down,
+    Left,
+    Right,
+    Up,
 }
 
 impl Direction {
     fn turn(&self, clockwise: bool) -> Direction {
         match self {
-            Direction::DOWN if clockwise => Direction::LEFT,
-            Direction::DOWN => Direction::RIGHT,
-            Direction::LEFT if clockwise => Direction::UP,
-            Direction::LEFT => Direction::DOWN,
-            Direction::RIGHT if clockwise => Direction::DOWN,
-            Direction::RIGHT => Direction::UP,
-            Direction::UP if clockwise => Direction::RIGHT,
-            Direction::UP => Direction::LEFT,
+            Direction::Down if clockwise => Direction::Left,
+            Direction::Down => Direction::Right,
+            Direction::Left if clockwise => Direction::Up,
+            Direction::Left => Direction::Down,
+            Direction::Right if clockwise => Direction::Down,
+            Direction::Right => Direction::Up,
+            Direction::Up if clockwise => Direction::Right,
+            Direction::Up => Direction::Left,
         }
     }
 
     fn move_from(&self, position: Square) -> Square {
         match self {
-            Direction::DOWN => (position.0, position.1 + 1),
-            Direction::LEFT => (position.0 - 1, position.1),
-            Direction::RIGHT => (position.0 + 1, position.1),
-            Direction::UP => (position.0, position.1 - 1),
+            Direction::Down => (position.0, position.1 + 1),
+            Direction::Left => (position.0 - 1, position.1),
+            Direction::Right => (position.0 + 1, position.1),
+            Direction::Up => (position.0, position.1 - 1),
         }
     }
 }
@@ -108,46 +109,58 @@ impl Panel {
     }
 }
 
-fn to_bool(v: i64) -> bool {
+fn to_bool(v: i64) -> Result<bool, SolveError> {
     match v {
-        0 => false,
-        1 => true,
-        x => panic!("Expected 0 or 1, got {}", x),
+        0 => Ok(false),
+        1 => Ok(true),
+        x => Err(SolveError::new(format!("expected 0 or 1, got {}", x))),
     }
 }
 
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d11.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
-fn paint_hull(initial: impl Iterator<Item = Square>) -> Panel {
-    let program = &mut setup_intcode();
+/// Runs the robot until it outputs a value, returning `None` once it halts
+fn next_output(program: &mut Intcode, input: Option<i64>) -> Result<Option<i64>, SolveError> {
+    let state = program
+        .run_until_output(input.into_iter())
+        .context("Intcode program failed")?;
+    match state {
+        RunState::OutputReady(_) => Ok(program.next()),
+        RunState::Halted => Ok(None),
+        RunState::AwaitingInput => Err(SolveError::new("robot asked for more than one input")),
+        RunState::Cancelled | RunState::OutOfBudget => Err(SolveError::timed_out()),
+    }
+}
+
+fn paint_hull(data: &str, initial: impl Iterator<Item = Square>) -> Result<Panel, SolveError> {
+    let program = &mut parse(data)?;
     let mut panel = Panel::new();
     initial.for_each(|s| panel.set(s, true));
 
     let mut position: Square = (0, 0);
-    let mut direction = Direction::UP;
+    let mut direction = Direction::Up;
     loop {
-        program.run(once(panel.get(position) as i64));
-        let output: Vec<i64> = program.collect();
-        if output.len() == 2 {
-            panel.set(position, to_bool(output[0]));
-            direction = direction.turn(to_bool(output[1]));
-            position = direction.move_from(position);
-        } else if program.finished() {
-            break;
-        } else {
-            panic!("Program not finished but got invalid output {:?}", output);
-        }
+        cancel::check()?;
+        let colour = match next_output(program, Some(panel.get(position) as i64))? {
+            Some(c) => c,
+            None => break,
+        };
+        let turn = next_output(program, None)?.context("robot halted before turning")?;
+        panel.set(position, to_bool(colour)?);
+        direction = direction.turn(to_bool(turn)?);
+        position = direction.move_from(position);
     }
-    panel
+    Ok(panel)
 }
 
-pub fn part_a() -> String {
-    paint_hull(empty()).total_painted().to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    Ok(paint_hull(data, empty())?.total_painted().to_string())
 }
 
-pub fn part_b() -> String {
-    paint_hull(once((0, 0))).render().unwrap()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    paint_hull(data, once((0, 0)))?
+        .render()
+        .context("no panels were painted")
 }
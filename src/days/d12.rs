@@ -5,6 +5,9 @@ use std::str::FromStr;
 use itertools::Itertools;
 use regex::{Captures, Regex};
 
+use super::SolveError;
+use crate::cancel;
+
 lazy_static! {
     static ref RE: Regex = Regex::new("<x=(-?\\d+),\\s*y=(-?\\d+),\\s*z=(-?\\d+)>").unwrap();
 }
@@ -30,7 +33,7 @@ fn fold_lcm(v: Vec<u64>) -> u64 {
 type V = i32;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
-struct Moon {
+pub struct Moon {
     position: (V, V, V),
     velocity: (V, V, V),
 }
@@ -99,15 +102,13 @@ impl FromStr for Moon {
     }
 }
 
-fn parse_input() -> Vec<Moon> {
-    let data = include_bytes!("input/d12.txt");
-    String::from_utf8_lossy(data)
-        .split('\n')
-        .map(|s| s.parse::<Moon>().unwrap())
+pub fn parse(data: &str) -> Result<Vec<Moon>, SolveError> {
+    data.split('\n')
+        .map(|s| s.parse::<Moon>().map_err(SolveError::new))
         .collect()
 }
 
-fn simulate_system_step(moons: &mut Vec<Moon>) {
+fn simulate_system_step(moons: &mut [Moon]) {
     for pair in (0..moons.len()).combinations(2) {
         let (i, j) = (pair[0], pair[1]);
         let g0 = moons[i].gravity_effect(&moons[j]);
@@ -120,50 +121,51 @@ fn simulate_system_step(moons: &mut Vec<Moon>) {
     }
 }
 
-pub fn part_a() -> String {
-    let mut moons = parse_input();
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let mut moons = parse(data)?;
     for _ in 0..1000 {
         simulate_system_step(&mut moons);
     }
 
-    moons
+    Ok(moons
         .iter()
         .map(|b| b.total_energy())
         .sum::<V>()
-        .to_string()
+        .to_string())
 }
 
-fn x_coords(moons: &Vec<Moon>) -> Vec<V> {
+fn x_coords(moons: &[Moon]) -> Vec<V> {
     moons.iter().map(|m| m.position.0).collect()
 }
 
-fn y_coords(moons: &Vec<Moon>) -> Vec<V> {
+fn y_coords(moons: &[Moon]) -> Vec<V> {
     moons.iter().map(|m| m.position.1).collect()
 }
 
-fn z_coords(moons: &Vec<Moon>) -> Vec<V> {
+fn z_coords(moons: &[Moon]) -> Vec<V> {
     moons.iter().map(|m| m.position.2).collect()
 }
 
-fn x_velocity_zero(moons: &Vec<Moon>) -> bool {
+fn x_velocity_zero(moons: &[Moon]) -> bool {
     moons.iter().all(|m| m.velocity.0 == 0)
 }
 
-fn y_velocity_zero(moons: &Vec<Moon>) -> bool {
+fn y_velocity_zero(moons: &[Moon]) -> bool {
     moons.iter().all(|m| m.velocity.1 == 0)
 }
 
-fn z_velocity_zero(moons: &Vec<Moon>) -> bool {
+fn z_velocity_zero(moons: &[Moon]) -> bool {
     moons.iter().all(|m| m.velocity.2 == 0)
 }
 
-pub fn part_b() -> String {
-    let mut moons = parse_input();
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let mut moons = parse(data)?;
     let initial = (x_coords(&moons), y_coords(&moons), z_coords(&moons));
 
     let mut found: (u64, u64, u64) = (0, 0, 0);
     let mut step: u64 = 0;
     loop {
+        cancel::check()?;
         simulate_system_step(&mut moons);
         step += 1;
         if found.0 == 0 && x_velocity_zero(&moons) && x_coords(&moons) == initial.0 {
@@ -175,9 +177,9 @@ pub fn part_b() -> String {
         if found.2 == 0 && z_velocity_zero(&moons) && z_coords(&moons) == initial.2 {
             found.2 = step;
         }
-        if found.0 > 0 && found.0 > 1 && found.2 > 0 {
+        if found.0 > 0 && found.1 > 0 && found.2 > 0 {
             break;
         }
     }
-    fold_lcm(vec![found.0, found.1, found.2]).to_string()
+    Ok(fold_lcm(vec![found.0, found.1, found.2]).to_string())
 }
@@ -1,10 +1,10 @@
 /// Advent of Code 2019, day 13
 /// https://adventofcode.com/2019/day/13
 use std::collections::HashMap;
-use std::iter::{empty, once};
-use std::str::from_utf8;
+use std::iter::empty;
 
-use super::intcode::Intcode;
+use super::{Context, SolveError};
+use crate::intcode::{Intcode, IntcodeIo, RunState};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Tile {
@@ -16,14 +16,14 @@ enum Tile {
 }
 
 impl Tile {
-    fn from(i: i64) -> Self {
+    fn from(i: i64) -> Result<Self, SolveError> {
         match i {
-            0 => Tile::Empty,
-            1 => Tile::Wall,
-            2 => Tile::Block,
-            3 => Tile::Paddle,
-            4 => Tile::Ball,
-            x => panic!("Tile {} is not valid.", x),
+            0 => Ok(Tile::Empty),
+            1 => Ok(Tile::Wall),
+            2 => Ok(Tile::Block),
+            3 => Ok(Tile::Paddle),
+            4 => Ok(Tile::Ball),
+            x => Err(SolveError::new(format!("tile {} is not valid", x))),
         }
     }
 
@@ -60,12 +60,13 @@ impl Screen {
         self.tiles.iter().filter(|(_, &t)| t == Tile::Block).count()
     }
 
-    fn set(&mut self, x: i64, y: i64, t: i64) {
+    fn set(&mut self, x: i64, y: i64, t: i64) -> Result<(), SolveError> {
         if (x, y) != (-1, 0) {
-            self.tiles.insert((x, y), Tile::from(t));
+            self.tiles.insert((x, y), Tile::from(t)?);
         } else {
             self.score = t;
         }
+        Ok(())
     }
 
     fn bottom(&self) -> i64 {
@@ -100,56 +101,104 @@ impl Screen {
     }
 }
 
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d13.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
-pub fn part_a() -> String {
-    let mut program = setup_intcode();
-    let mut screen = Screen::new();
-    program.run(empty());
-    for o in program.collect::<Vec<i64>>().chunks(3) {
-        screen.set(o[0], o[1], o[2]);
+fn check_output(output: &[i64]) -> Result<(), SolveError> {
+    if !output.len().is_multiple_of(3) {
+        return Err(SolveError::new(format!(
+            "output of length {} cannot be split into tiles",
+            output.len()
+        )));
     }
-    screen.blocks().to_string()
+    Ok(())
 }
 
-pub fn part_b() -> String {
-    let mut program = setup_intcode();
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let mut program = parse(data)?;
     let mut screen = Screen::new();
-    program.set_value(0, 2);
-    program.run(empty());
-
-    let mut ball: (i64, i64) = (0, 0);
-    let mut paddle: (i64, i64) = (0, 0);
-    loop {
-        let input = if paddle.0 > ball.0 {
-            -1
-        } else if paddle.0 < ball.0 {
-            1
-        } else {
-            0
-        };
-        program.run(once(input));
-
-        let output: Vec<i64> = program.drain().collect();
-        for o in output.chunks(3) {
-            if (o[0], o[1]) != (-1, 0) {
-                match Tile::from(o[2]) {
-                    Tile::Ball => ball = (o[0], o[1]),
-                    Tile::Paddle => paddle = (o[0], o[1]),
-                    _ => (),
-                }
+    program.run(empty()).context("Intcode program failed")?;
+    let output = program.collect::<Vec<i64>>();
+    check_output(&output)?;
+    for o in output.chunks(3) {
+        screen.set(o[0], o[1], o[2])?;
+    }
+    Ok(screen.blocks().to_string())
+}
+
+/// Game played by moving the joystick towards the ball whenever the program reads it
+struct Game {
+    screen: Screen,
+    /// Values output since the last complete tile
+    pending: Vec<i64>,
+    ball: i64,
+    paddle: i64,
+    error: Option<SolveError>,
+}
+
+impl Game {
+    fn new() -> Self {
+        Self {
+            screen: Screen::new(),
+            pending: Vec::new(),
+            ball: 0,
+            paddle: 0,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, x: i64, y: i64, t: i64) -> Result<(), SolveError> {
+        if (x, y) != (-1, 0) {
+            match Tile::from(t)? {
+                Tile::Ball => self.ball = x,
+                Tile::Paddle => self.paddle = x,
+                _ => (),
             }
-            screen.set(o[0], o[1], o[2]);
         }
+        self.screen.set(x, y, t)
+    }
+}
 
-        // println!("{}", screen.draw());
-        if screen.blocks() == 0 {
-            break;
+impl IntcodeIo for Game {
+    fn input(&mut self) -> Option<i64> {
+        // Stop the program once the output has gone wrong
+        match self.error {
+            Some(_) => None,
+            None => Some((self.ball - self.paddle).signum()),
         }
     }
 
-    screen.score().to_string()
+    fn output(&mut self, value: i64) {
+        self.pending.push(value);
+        if let [x, y, t] = self.pending[..] {
+            self.pending.clear();
+            if let Err(err) = self.update(x, y, t) {
+                self.error.get_or_insert(err);
+            }
+        }
+    }
+}
+
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let mut program = parse(data)?;
+    let mut game = Game::new();
+    program.set_value(0, 2);
+    let state = program
+        .run_with(&mut game)
+        .context("Intcode program failed")?;
+
+    // println!("{}", game.screen.draw());
+    if let Some(err) = game.error {
+        return Err(err);
+    }
+    check_output(&game.pending)?;
+    match state {
+        RunState::Cancelled => Err(SolveError::timed_out()),
+        _ if game.screen.blocks() > 0 => Err(SolveError::new(format!(
+            "game ended with {} blocks remaining",
+            game.screen.blocks()
+        ))),
+        _ => Ok(game.screen.score().to_string()),
+    }
 }
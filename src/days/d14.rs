@@ -4,8 +4,10 @@ use std::collections::{HashMap, VecDeque};
 use std::iter::FromIterator;
 use std::str::FromStr;
 
+use super::SolveError;
+
 fn div_round_up(n: u64, d: u64) -> u64 {
-    if n % d != 0 {
+    if !n.is_multiple_of(d) {
         n / d + 1
     } else {
         n / d
@@ -67,7 +69,7 @@ impl FromStr for Recipe {
 }
 
 #[derive(Debug, Clone)]
-struct Collection {
+pub struct Collection {
     recipes: Vec<Recipe>,
 }
 
@@ -76,7 +78,7 @@ impl Collection {
         Self { recipes }
     }
 
-    fn find<'a>(&self, material: &String) -> Option<usize> {
+    fn find(&self, material: &String) -> Option<usize> {
         self.recipes
             .iter()
             .enumerate()
@@ -102,7 +104,7 @@ impl Collection {
         base
     }
 
-    fn sorted<'a>(&self) -> Vec<String> {
+    fn sorted(&self) -> Result<Vec<String>, SolveError> {
         let mut sorted = Vec::new();
         let mut base: VecDeque<String> = VecDeque::from_iter(self.base().iter().cloned());
         let mut g = self.clone();
@@ -119,19 +121,21 @@ impl Collection {
                         r.input.remove(j);
                     }
                 }
-                if r.input.len() == 0 {
+                if r.input.is_empty() {
                     base.push_front(r.output.material.clone());
                     g.recipes.remove(i);
                 }
             }
         }
-        if g.recipes.len() > 0 {
-            panic!("DAG not consumed while sorting; may be a cycle somewhere.");
+        if !g.recipes.is_empty() {
+            return Err(SolveError::new(
+                "DAG not consumed while sorting; may be a cycle somewhere",
+            ));
         }
-        sorted
+        Ok(sorted)
     }
 
-    fn find_total_raw_from_sort(&self, sorted: &Vec<String>, material: &Material) -> Vec<Material> {
+    fn find_total_raw_from_sort(&self, sorted: &[String], material: &Material) -> Vec<Material> {
         let base = self.base();
         let mut materials: HashMap<&String, u64> = HashMap::new();
         materials.insert(&material.material, material.quantity);
@@ -143,7 +147,7 @@ impl Collection {
             if let Some(i) = self.find(m) {
                 let r = &self.recipes[i];
                 let multi = div_round_up(
-                    materials.get(&r.output.material).unwrap().clone(),
+                    *materials.get(&r.output.material).unwrap(),
                     r.output.quantity,
                 );
                 for i in r.input.iter() {
@@ -156,7 +160,7 @@ impl Collection {
             .iter()
             .filter_map(|(&m, q)| {
                 if base.contains(m) {
-                    Some(Material::new(m.clone(), q.clone()))
+                    Some(Material::new(m.clone(), *q))
                 } else {
                     None
                 }
@@ -164,42 +168,51 @@ impl Collection {
             .collect()
     }
 
-    fn total_raw(&self, material: &Material) -> Vec<Material> {
-        let sorted = self.sorted();
-        self.find_total_raw_from_sort(&sorted, material)
+    fn total_raw(&self, material: &Material) -> Result<Vec<Material>, SolveError> {
+        let sorted = self.sorted()?;
+        Ok(self.find_total_raw_from_sort(&sorted, material))
     }
 }
 
-fn parse_input<'a>() -> Collection {
-    let data = include_bytes!("input/d14.txt");
-    let recipes = String::from_utf8_lossy(data)
-        .split("\n")
+pub fn parse(data: &str) -> Result<Collection, SolveError> {
+    let recipes = data
+        .split('\n')
         .map(|l| l.parse::<Recipe>())
         .collect::<Result<Vec<Recipe>, String>>()
-        .unwrap();
-    Collection::new(recipes)
+        .map_err(SolveError::new)?;
+    Ok(Collection::new(recipes))
+}
+
+fn ore_required(result: &[Material]) -> Result<u64, SolveError> {
+    match result {
+        [m] => Ok(m.quantity),
+        _ => Err(SolveError::new(format!(
+            "expected FUEL to be made from one raw material, got {}",
+            result.len()
+        ))),
+    }
 }
 
-pub fn part_a() -> String {
-    let result = parse_input().total_raw(&Material::new("FUEL".to_string(), 1));
-    result[0].quantity.to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let result = parse(data)?.total_raw(&Material::new("FUEL".to_string(), 1))?;
+    Ok(ore_required(&result)?.to_string())
 }
 
-pub fn part_b() -> String {
-    let collection = &parse_input();
-    let sorted = collection.sorted();
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let collection = &parse(data)?;
+    let sorted = collection.sorted()?;
     let fuel = "FUEL".to_string();
     let result = collection.find_total_raw_from_sort(&sorted, &Material::new(fuel.clone(), 1));
 
     let total: u64 = 1000000000000;
-    let mut min = total / result[0].quantity;
+    let mut min = total / ore_required(&result)?;
     let mut max = min * 2;
 
     while max - min > 1 {
         let mid = (min + max) / 2;
         let result =
             collection.find_total_raw_from_sort(&sorted, &Material::new(fuel.clone(), mid));
-        let required = result[0].quantity;
+        let required = ore_required(&result)?;
         if required > total {
             max = mid;
         } else if required < total {
@@ -210,5 +223,5 @@ pub fn part_b() -> String {
         }
     }
 
-    min.to_string()
+    Ok(min.to_string())
 }
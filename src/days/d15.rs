@@ -3,13 +3,13 @@
 use std::collections::{HashMap, VecDeque};
 use std::iter::once;
 use std::slice::Iter;
-use std::str::from_utf8;
 
-use super::intcode::Intcode;
+use super::{Context, SolveError};
+use crate::cancel;
+use crate::intcode::Intcode;
 
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d15.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
 type Point = (i32, i32);
@@ -22,27 +22,24 @@ enum Status {
 }
 
 impl Status {
-    fn from_i64(i: i64) -> Self {
+    fn from_i64(i: i64) -> Result<Self, SolveError> {
         match i {
-            0 => Status::Wall,
-            1 => Status::Empty,
-            2 => Status::OxygenSystem,
-            x => panic!("Output must be in range [0, 2], received {:?}", x),
+            0 => Ok(Status::Wall),
+            1 => Ok(Status::Empty),
+            2 => Ok(Status::OxygenSystem),
+            x => Err(SolveError::new(format!(
+                "output must be in range [0, 2], received {:?}",
+                x
+            ))),
         }
     }
 
     fn has_moved(&self) -> bool {
-        match self {
-            Status::Empty | Status::OxygenSystem => true,
-            _ => false,
-        }
+        matches!(self, Status::Empty | Status::OxygenSystem)
     }
 
     fn is_oxygen_system(&self) -> bool {
-        match self {
-            Status::OxygenSystem => true,
-            _ => false,
-        }
+        matches!(self, Status::OxygenSystem)
     }
 }
 
@@ -100,25 +97,30 @@ impl Droid {
         }
     }
 
-    fn run(&mut self, d: Direction) -> Status {
-        self.program.run(once(d.as_i64()));
+    fn run(&mut self, d: Direction) -> Result<Status, SolveError> {
+        self.program
+            .run(once(d.as_i64()))
+            .context("Intcode program failed")?;
         let output: Vec<i64> = self.program.drain().collect();
         if output.len() != 1 {
-            panic!("Single output expected, received {:?}", output);
+            return Err(SolveError::new(format!(
+                "single output expected, received {:?}",
+                output
+            )));
         }
-        let status = Status::from_i64(output[0]);
+        let status = Status::from_i64(output[0])?;
         if status.has_moved() {
             self.position = d.move_from(self.position);
             self.travelled += 1;
         }
-        status
+        Ok(status)
     }
 
     fn reset(&mut self) {
         self.travelled = 0;
     }
 
-    fn extend(&self, discovered: &mut HashMap<Point, Status>) -> Vec<Self> {
+    fn extend(&self, discovered: &mut HashMap<Point, Status>) -> Result<Vec<Self>, SolveError> {
         let mut new_droids = Vec::new();
         for d in Direction::iter() {
             let next = d.move_from(self.position);
@@ -126,39 +128,40 @@ impl Droid {
                 continue;
             }
             let mut droid = self.clone();
-            let status = droid.run(*d);
+            let status = droid.run(*d)?;
             if status.has_moved() {
                 new_droids.push(droid);
             }
             discovered.insert(next, status);
         }
-        new_droids
+        Ok(new_droids)
     }
 }
 
-fn find_oxygen_system(code: &Intcode) -> Option<Droid> {
+fn find_oxygen_system(code: &Intcode) -> Result<Droid, SolveError> {
     let mut discovered: HashMap<Point, Status> = HashMap::new();
     let mut queue: VecDeque<Droid> = VecDeque::new();
     queue.push_front(Droid::new(code));
-    loop {
-        let droid = queue.pop_back()?;
-        for d in droid.extend(&mut discovered).drain(..) {
-            if discovered.get(&d.position).unwrap().is_oxygen_system() {
-                return Some(d);
+    while let Some(droid) = queue.pop_back() {
+        cancel::check()?;
+        for d in droid.extend(&mut discovered)?.drain(..) {
+            if discovered[&d.position].is_oxygen_system() {
+                return Ok(d);
             }
             queue.push_front(d);
         }
     }
+    Err(SolveError::new("oxygen system not found"))
 }
 
-pub fn part_a() -> String {
-    let code = setup_intcode();
-    find_oxygen_system(&code).unwrap().travelled.to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let code = parse(data)?;
+    Ok(find_oxygen_system(&code)?.travelled.to_string())
 }
 
-pub fn part_b() -> String {
-    let code = setup_intcode();
-    let mut start = find_oxygen_system(&code).unwrap();
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let code = parse(data)?;
+    let mut start = find_oxygen_system(&code)?;
     start.reset();
 
     let mut discovered: HashMap<Point, Status> = HashMap::new();
@@ -167,7 +170,8 @@ pub fn part_b() -> String {
 
     let mut greatest: u32 = 0;
     while let Some(droid) = queue.pop_back() {
-        for d in droid.extend(&mut discovered).drain(..) {
+        cancel::check()?;
+        for d in droid.extend(&mut discovered)?.drain(..) {
             if d.travelled > greatest {
                 greatest = d.travelled;
             }
@@ -175,5 +179,5 @@ pub fn part_b() -> String {
         }
     }
 
-    greatest.to_string()
+    Ok(greatest.to_string())
 }
@@ -1,9 +1,9 @@
 /// Advent of Code 2019, day 16
 /// https://adventofcode.com/2019/day/16
-use std::str::from_utf8;
-
 use itertools::Itertools;
 
+use super::{Context, SolveError};
+
 type S = i32;
 
 const CYCLES: usize = 100;
@@ -75,23 +75,33 @@ fn apply_transform(signal: &[S], cycles: usize, repeat: usize, offset_digits: us
     data
 }
 
-fn parse_input() -> Vec<S> {
-    let data = include_bytes!("input/d16.txt");
-    from_utf8(data)
-        .unwrap()
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as S)
+pub fn parse(data: &str) -> Result<Vec<S>, SolveError> {
+    data.chars()
+        .map(|c| {
+            c.to_digit(10)
+                .map(|d| d as S)
+                .context(format!("{:?} is not a digit", c))
+        })
         .collect()
 }
 
-pub fn part_a() -> String {
-    let data = apply_transform(&parse_input(), CYCLES, 1, 0);
-    data[..8].iter().join("")
+fn message(signal: &[S]) -> Result<String, SolveError> {
+    let message = signal.get(..8).context("signal is shorter than 8 digits")?;
+    Ok(message.iter().join(""))
 }
 
-pub fn part_b() -> String {
-    let data: Vec<S> = apply_transform(&parse_input(), CYCLES, MULTIPLIER, OFFSET);
-    data[..8].iter().join("")
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let data = apply_transform(&parse(data)?, CYCLES, 1, 0);
+    message(&data)
+}
+
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let signal = parse(data)?;
+    if signal.len() < OFFSET || read_offset(&signal, OFFSET) >= signal.len() * MULTIPLIER {
+        return Err(SolveError::new("message offset is outside the signal"));
+    }
+    let data: Vec<S> = apply_transform(&signal, CYCLES, MULTIPLIER, OFFSET);
+    message(&data)
 }
 
 #[cfg(test)]
@@ -1,14 +1,20 @@
 /// Advent of Code 2019, day 17
 /// https://adventofcode.com/2019/day/17
 use std::collections::HashMap;
-use std::iter::empty;
 use std::slice::Iter;
-use std::str::from_utf8;
 
-use super::intcode::Intcode;
+use super::{Context, SolveError};
+use crate::intcode::ascii::AsciiIntcode;
+use crate::intcode::Intcode;
 
 type C = (i32, i32);
 
+/// Longest movement routine or function the robot accepts, not counting the newline
+const MAX_ROUTINE: usize = 20;
+
+/// Names of the movement functions
+const FUNCTIONS: [&str; 3] = ["A", "B", "C"];
+
 #[derive(Debug, Clone)]
 enum Direction {
     Unknown,
@@ -19,14 +25,17 @@ enum Direction {
 }
 
 impl Direction {
-    fn from_char(c: char) -> Direction {
+    fn from_char(c: char) -> Result<Direction, SolveError> {
         match c {
-            'X' => Direction::Unknown,
-            'v' => Direction::Down,
-            '<' => Direction::Left,
-            '>' => Direction::Right,
-            '^' => Direction::Up,
-            x => panic!("Unknown character for direction: {}", x),
+            'X' => Ok(Direction::Unknown),
+            'v' => Ok(Direction::Down),
+            '<' => Ok(Direction::Left),
+            '>' => Ok(Direction::Right),
+            '^' => Ok(Direction::Up),
+            x => Err(SolveError::new(format!(
+                "unknown character for direction: {:?}",
+                x
+            ))),
         }
     }
 
@@ -50,6 +59,20 @@ impl Direction {
         }
     }
 
+    fn left(&self) -> Direction {
+        match self {
+            Direction::Unknown => Direction::Unknown,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Down,
+            Direction::Right => Direction::Up,
+            Direction::Up => Direction::Left,
+        }
+    }
+
+    fn right(&self) -> Direction {
+        self.left().left().left()
+    }
+
     fn step(&self, position: C) -> C {
         match self {
             Direction::Unknown => position,
@@ -75,18 +98,18 @@ impl Scaffold {
         }
     }
 
-    fn from_output(output: impl Iterator<Item = i64>) -> Self {
+    fn from_output(output: &str) -> Result<Self, SolveError> {
         let mut s = Self::new();
-        s.update(output);
-        s
+        s.update(output)?;
+        Ok(s)
     }
 
-    fn update(&mut self, output: impl Iterator<Item = i64>) {
+    fn update(&mut self, output: &str) -> Result<(), SolveError> {
         let mut newline;
         let mut position = (0, 0);
-        for i in output {
+        for c in output.chars() {
             newline = false;
-            match i as u8 as char {
+            match c {
                 '#' => {
                     self.map.insert(position, true);
                 }
@@ -95,10 +118,15 @@ impl Scaffold {
                 }
                 d @ 'v' | d @ '<' | d @ '>' | d @ '^' | d @ 'X' => {
                     self.map.insert(position, d != 'X');
-                    self.robot = Some((position, Direction::from_char(d)));
+                    self.robot = Some((position, Direction::from_char(d)?));
                 }
                 '\n' => newline = true,
-                c => panic!("Received unknown char {:?} ({}) in output", c, i),
+                c => {
+                    return Err(SolveError::new(format!(
+                        "received unknown char {:?} in output",
+                        c
+                    )))
+                }
             };
             if newline {
                 position.0 = 0;
@@ -107,6 +135,7 @@ impl Scaffold {
                 position.0 += 1;
             }
         }
+        Ok(())
     }
 
     fn adjacent(&self, c: C, d: Direction) -> bool {
@@ -125,6 +154,46 @@ impl Scaffold {
             .collect()
     }
 
+    fn is_scaffold(&self, c: C) -> bool {
+        *self.map.get(&c).unwrap_or(&false)
+    }
+
+    /// Moves the robot makes following the scaffold, going straight over intersections, until it
+    /// reaches the end. Each move is a turn, if needed, and a number of steps, such as `R,8`.
+    fn path(&self) -> Result<Vec<String>, SolveError> {
+        let (mut position, mut direction) = match &self.robot {
+            Some((_, Direction::Unknown)) => {
+                return Err(SolveError::new("robot has fallen off the scaffold"))
+            }
+            Some((position, direction)) => (*position, direction.clone()),
+            None => return Err(SolveError::new("no robot on the map")),
+        };
+        let mut path = Vec::new();
+        loop {
+            let mut turn = None;
+            if !self.is_scaffold(direction.step(position)) {
+                if self.is_scaffold(direction.left().step(position)) {
+                    turn = Some('L');
+                    direction = direction.left();
+                } else if self.is_scaffold(direction.right().step(position)) {
+                    turn = Some('R');
+                    direction = direction.right();
+                } else {
+                    return Ok(path);
+                }
+            }
+            let mut steps = 0;
+            while self.is_scaffold(direction.step(position)) {
+                position = direction.step(position);
+                steps += 1;
+            }
+            path.push(match turn {
+                Some(turn) => format!("{},{}", turn, steps),
+                None => steps.to_string(),
+            });
+        }
+    }
+
     #[allow(dead_code)]
     fn draw(&self) {
         let bottom = self.map.keys().map(|c| c.1).max().unwrap();
@@ -150,41 +219,162 @@ impl Scaffold {
     }
 }
 
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d17.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
-pub fn part_a() -> String {
-    let mut program = setup_intcode();
-    program.run(empty());
-    let scaffold = Scaffold::from_output(program.drain());
+fn routine_len(moves: &[String]) -> usize {
+    moves
+        .iter()
+        .map(|m| m.len() + 1)
+        .sum::<usize>()
+        .saturating_sub(1)
+}
+
+/// Splits what is left of the path into calls to movement functions, adding new functions while
+/// there are fewer than three
+fn compress<'a>(
+    path: &'a [String],
+    functions: &mut Vec<&'a [String]>,
+    main: &mut Vec<usize>,
+) -> bool {
+    if path.is_empty() {
+        return true;
+    }
+    if 2 * main.len() + 1 > MAX_ROUTINE {
+        return false;
+    }
+    for i in 0..functions.len() {
+        if path.starts_with(functions[i]) {
+            main.push(i);
+            if compress(&path[functions[i].len()..], functions, main) {
+                return true;
+            }
+            main.pop();
+        }
+    }
+    if functions.len() < FUNCTIONS.len() {
+        for n in 1..=path.len() {
+            if routine_len(&path[..n]) > MAX_ROUTINE {
+                break;
+            }
+            main.push(functions.len());
+            functions.push(&path[..n]);
+            if compress(&path[n..], functions, main) {
+                return true;
+            }
+            functions.pop();
+            main.pop();
+        }
+    }
+    false
+}
+
+/// Main movement routine followed by each of the functions it calls, as the lines to send the
+/// robot
+fn movement_routine(path: &[String]) -> Result<Vec<String>, SolveError> {
+    let mut functions = Vec::new();
+    let mut main = Vec::new();
+    if !compress(path, &mut functions, &mut main) {
+        return Err(SolveError::new(format!(
+            "path can't be split into {} movement functions: {}",
+            FUNCTIONS.len(),
+            path.join(",")
+        )));
+    }
+    let mut lines = vec![main
+        .iter()
+        .map(|&i| FUNCTIONS[i])
+        .collect::<Vec<_>>()
+        .join(",")];
+    lines.extend(functions.iter().map(|f| f.join(",")));
+    // Functions that aren't needed still have to be given
+    lines.resize(1 + FUNCTIONS.len(), "L".to_string());
+    Ok(lines)
+}
+
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let mut program = AsciiIntcode::new(parse(data)?);
+    program.run().context("Intcode program failed")?;
+    let scaffold = Scaffold::from_output(&program.take_text())?;
     // scaffold.draw();
-    scaffold
+    Ok(scaffold
         .intersections()
         .iter()
         .map(|&c| c.0 * c.1)
         .sum::<i32>()
-        .to_string()
+        .to_string())
 }
 
-fn stream_chars(s: &str) -> impl Iterator<Item = i64> + '_ {
-    s.chars().map(|c| c as u8 as i64)
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let mut program = AsciiIntcode::new(parse(data)?);
+    program.run().context("Intcode program failed")?;
+    let scaffold = Scaffold::from_output(&program.take_text())?;
+    let mut lines = movement_routine(&scaffold.path()?)?;
+    lines.push("n".to_string());
+
+    let mut program = parse(data)?;
+    program.set_value(0, 2);
+    let mut program = AsciiIntcode::new(program);
+    for line in &lines {
+        program
+            .send_line(line)
+            .context("invalid movement routine")?;
+    }
+    program.run().context("Intcode program failed")?;
+
+    match program.results().last() {
+        Some(dust) => Ok(dust.to_string()),
+        _ => Err(SolveError::new(
+            "robot did not report any dust; the movement routine may not fit this scaffold",
+        )),
+    }
 }
 
-pub fn part_b() -> String {
-    let mut program = setup_intcode();
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    program.set_value(0, 2);
-    program.run(empty());
-    program.run(stream_chars(
-        "A,B,A,B,C,C,B,C,B,A\n\
-         R,12,L,8,R,12\n\
-         R,8,R,6,R,6,R,8\n\
-         R,8,L,8,R,8,R,4,R,4\n\
-         n\n",
-    ));
-
-    let output = program.drain().collect::<Vec<i64>>();
-    output[output.len() - 1].to_string()
+    #[test]
+    fn test_movement_routine() {
+        let map = "\
+            #######...#####\n\
+            #.....#...#...#\n\
+            #.....#...#...#\n\
+            ......#...#...#\n\
+            ......#...###.#\n\
+            ......#.....#.#\n\
+            ^########...#.#\n\
+            ......#.#...#.#\n\
+            ......#########\n\
+            ........#...#..\n\
+            ....#########..\n\
+            ....#...#......\n\
+            ....#...#......\n\
+            ....#...#......\n\
+            ....#####......\n";
+        let path = Scaffold::from_output(map).unwrap().path().unwrap();
+        assert_eq!(
+            path.join(","),
+            "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+        );
+
+        let lines = movement_routine(&path).unwrap();
+        assert!(lines.iter().all(|line| line.len() <= MAX_ROUTINE));
+        let expanded = lines[0]
+            .split(',')
+            .map(|f| lines[1 + FUNCTIONS.iter().position(|&n| n == f).unwrap()].as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        assert_eq!(expanded, path.join(","));
+
+        // Unused functions are still sent
+        assert_eq!(
+            movement_routine(&["L,1".to_string()]).unwrap(),
+            vec!["A", "L,1", "L", "L"]
+        );
+        // Functions fit at most four of these moves, so 13 different ones can't be covered
+        let path = (10..23).map(|i| format!("R,{}", i)).collect::<Vec<_>>();
+        assert!(movement_routine(&path).is_err());
+    }
 }
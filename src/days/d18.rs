@@ -2,10 +2,14 @@
 /// https://adventofcode.com/2019/day/18
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 use std::hash::Hash;
 use std::iter::{once, FromIterator};
-use std::str::{from_utf8, FromStr};
+use std::str::FromStr;
+
+use super::{Context, SolveError};
+use crate::cancel;
 
 fn char_to_u32(c: char) -> Option<u32> {
     if c.is_ascii_uppercase() {
@@ -19,7 +23,7 @@ fn char_to_u32(c: char) -> Option<u32> {
 
 fn char_from_u32(i: u32) -> Option<char> {
     if i < 26 {
-        Some((i as u8 + ('A' as u8)) as char)
+        Some((i as u8 + b'A') as char)
     } else {
         None
     }
@@ -51,20 +55,6 @@ impl Keys {
         self.0 & 1 << char_to_u32(c).unwrap() > 0
     }
 
-    fn complete(&self) -> bool {
-        let mut f = self.0;
-        if f == 0 {
-            return false;
-        }
-        while f > 0 {
-            if (f & 1) == 0 {
-                return false;
-            }
-            f >>= 1;
-        }
-        true
-    }
-
     fn is_subset(&self, doors: Keys) -> bool {
         self.0 | doors.0 == self.0
     }
@@ -82,7 +72,7 @@ impl Display for Keys {
 }
 
 #[derive(Debug)]
-enum MapParseError {
+pub enum MapParseError {
     FeatureParseError(char),
     InvalidWidth,
     NoEntrance,
@@ -102,6 +92,8 @@ impl Display for MapParseError {
     }
 }
 
+impl Error for MapParseError {}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 enum Feature {
     Entrance,
@@ -124,10 +116,7 @@ impl Feature {
     }
 
     fn passable(&self) -> bool {
-        match self {
-            Feature::Wall => false,
-            _ => true,
-        }
+        !matches!(self, Feature::Wall)
     }
 }
 
@@ -145,7 +134,7 @@ impl Display for Feature {
 }
 
 #[derive(Debug)]
-struct Map {
+pub struct Map {
     features: Vec<Feature>,
     width: usize,
     height: usize,
@@ -179,7 +168,7 @@ impl Map {
         if p / self.width < self.height - 1 {
             adj.push(p + self.width);
         }
-        if p % self.width > 0 {
+        if !p.is_multiple_of(self.width) {
             adj.push(p - 1);
         }
         if p % self.width < self.width - 1 {
@@ -188,7 +177,7 @@ impl Map {
         adj
     }
 
-    fn find_keys(&self, p: usize) -> MapBFS {
+    fn find_keys(&self, p: usize) -> MapBFS<'_> {
         MapBFS::new(self, p)
     }
 
@@ -199,29 +188,37 @@ impl Map {
         }
     }
 
-    fn replace_entrance(&mut self) {
-        let entrance = {
-            let e = self.entrances();
-            if e.len() != 1 {
-                panic!("Replacing the entrance only works when there is a single entrance!");
+    fn replace_entrance(&mut self) -> Result<(), SolveError> {
+        let entrance = match self.entrances()[..] {
+            [e] => e,
+            _ => {
+                return Err(SolveError::new(
+                    "replacing the entrance only works when there is a single entrance",
+                ))
             }
-            e[0]
         };
+        let (x, y) = (entrance % self.width, entrance / self.width);
+        if x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1 {
+            return Err(SolveError::new("entrance is on the edge of the map"));
+        }
         let (above, below) = (entrance - self.width, entrance + self.width);
 
-        self.features[entrance] = Feature::Wall;
-        for &s in [above, entrance - 1, entrance + 1, below].iter() {
-            self.features[s] = match self.features[s] {
-                Feature::Floor => Feature::Wall,
-                _ => panic!("{} not a floor.", s),
+        let walls = [above, entrance - 1, entrance + 1, below];
+        let entrances = [above - 1, above + 1, below - 1, below + 1];
+        for &p in walls.iter().chain(entrances.iter()) {
+            if self.features[p] != Feature::Floor {
+                return Err(SolveError::new(format!("{} not a floor", p)));
             }
         }
-        for &d in [above - 1, above + 1, below - 1, below + 1].iter() {
-            self.features[d] = match self.features[d] {
-                Feature::Floor => Feature::Entrance,
-                _ => panic!("{} not a floor.", d),
-            }
+
+        self.features[entrance] = Feature::Wall;
+        for &s in walls.iter() {
+            self.features[s] = Feature::Wall;
         }
+        for &d in entrances.iter() {
+            self.features[d] = Feature::Entrance;
+        }
+        Ok(())
     }
 }
 
@@ -252,12 +249,9 @@ impl FromStr for Map {
         }
         let features = lines
             .iter()
-            .flat_map(|&l| l.chars().map(|b| Feature::from_char(b)))
+            .flat_map(|&l| l.chars().map(Feature::from_char))
             .collect::<Result<Vec<Feature>, Self::Err>>()?;
-        if !features.iter().any(|f| match f {
-            Feature::Entrance => true,
-            _ => false,
-        }) {
+        if !features.iter().any(|f| matches!(f, Feature::Entrance)) {
             Err(MapParseError::NoEntrance)?
         }
         Ok(Self::new(features, width, height))
@@ -323,35 +317,34 @@ fn build_adj_matrix(map: &Map) -> HashMap<usize, HashMap<usize, (Keys, usize)>>
             _ => None,
         });
     for start in map.entrances().iter().cloned().chain(iter_keys) {
+        // Every start gets an entry, even if no keys can be reached from it
+        let reachable: &mut HashMap<_, _> = matrix.entry(start).or_default();
         for (pos, dist, keys) in map.find_keys(start) {
-            matrix
-                .entry(start)
-                .or_insert(HashMap::new())
-                .insert(pos, (keys, dist));
+            reachable.insert(pos, (keys, dist));
         }
     }
     matrix
 }
 
 trait Position: Sized + Debug + Copy + Eq + Hash + AsRef<[usize]> + AsMut<[usize]> {
-    fn from_vec(v: Vec<usize>) -> Self;
+    fn from_vec(v: Vec<usize>) -> Option<Self>;
 }
 
 impl Position for [usize; 1] {
-    fn from_vec(v: Vec<usize>) -> Self {
-        if v.len() != 1 {
-            panic!("expected vec of size 1; got {}", v.len());
+    fn from_vec(v: Vec<usize>) -> Option<Self> {
+        match v[..] {
+            [a] => Some([a]),
+            _ => None,
         }
-        [v[0]]
     }
 }
 
 impl Position for [usize; 4] {
-    fn from_vec(v: Vec<usize>) -> Self {
-        if v.len() != 4 {
-            panic!("expected vec of size 4; got {}", v.len());
+    fn from_vec(v: Vec<usize>) -> Option<Self> {
+        match v[..] {
+            [a, b, c, d] => Some([a, b, c, d]),
+            _ => None,
         }
-        [v[0], v[1], v[2], v[3]]
     }
 }
 
@@ -384,21 +377,33 @@ impl<P: Position> PartialOrd for Path<P> {
     }
 }
 
-fn map_dijkstra<P: Position>(map: &Map) -> Option<usize> {
+fn map_dijkstra<P: Position>(map: &Map) -> Result<usize, SolveError> {
     let src = map.entrances();
+    let count = src.len();
 
+    let all_keys = map.features.iter().fold(Keys::new(), |keys, f| match f {
+        Feature::Key(c) => keys.with(*c),
+        _ => keys,
+    });
     let matrix = build_adj_matrix(map);
     let mut heap = BinaryHeap::<Path<P>>::new();
     let mut dist = HashMap::<(Keys, P), usize>::new();
 
-    let src_a = P::from_vec(src);
+    let src_a =
+        P::from_vec(src).context(format!("map has wrong number of entrances: {}", count))?;
     heap.push(Path::new(src_a, 0, Keys::new()));
     dist.insert((Keys::new(), src_a), 0);
 
     while let Some(path) = heap.pop() {
+        cancel::check()?;
         for (i, p) in path.position.as_ref().iter().enumerate() {
-            for (pos, (keys, d)) in matrix[p].iter() {
-                let c = map.get_key(*pos).unwrap();
+            let reachable = matrix
+                .get(p)
+                .context(format!("no paths were found from {}", p))?;
+            for (pos, (keys, d)) in reachable.iter() {
+                let c = map
+                    .get_key(*pos)
+                    .context(format!("path leads to {}, which is not a key", pos))?;
                 if path.keys.has(c) || !path.keys.is_subset(*keys) {
                     continue;
                 }
@@ -415,26 +420,37 @@ fn map_dijkstra<P: Position>(map: &Map) -> Option<usize> {
     }
 
     dist.iter()
-        .filter_map(|((k, _), &d)| match k.complete() {
+        .filter_map(|((k, _), &d)| match *k == all_keys {
             true => Some(d),
             false => None,
         })
         .min()
+        .context("not all keys can be collected")
+}
+
+pub fn parse(data: &str) -> Result<Map, SolveError> {
+    data.parse::<Map>().context("invalid map")
 }
 
-fn parse_map() -> Map {
-    let data = include_bytes!("input/d18.txt");
-    from_utf8(data).unwrap().parse::<Map>().unwrap()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    Ok(map_dijkstra::<[usize; 1]>(&parse(data)?)?.to_string())
 }
 
-pub fn part_a() -> String {
-    map_dijkstra::<[usize; 1]>(&parse_map())
-        .unwrap()
-        .to_string()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let mut map = parse(data)?;
+    map.replace_entrance()?;
+    Ok(map_dijkstra::<[usize; 4]>(&map)?.to_string())
 }
 
-pub fn part_b() -> String {
-    let mut map = parse_map();
-    map.replace_entrance();
-    map_dijkstra::<[usize; 4]>(&map).unwrap().to_string()
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_small_maps() {
+        assert_eq!(part_a("#####\n#@.a#\n#####").unwrap(), "2");
+        assert_eq!(part_a("#########\n#b.A.@.a#\n#########").unwrap(), "8");
+        assert_eq!(part_a("#####\n#@..#\n#####").unwrap(), "0");
+        assert!(part_a("#####\n#@#a#\n#####").is_err());
+    }
 }
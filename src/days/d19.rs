@@ -1,19 +1,23 @@
 /// Advent of Code 2019, day 19
 /// https://adventofcode.com/2019/day/19
 use std::collections::VecDeque;
-use std::str::from_utf8;
 
-use super::intcode::Intcode;
+use itertools::process_results;
 
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d19.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
+use super::{Context, SolveError};
+use crate::cancel;
+use crate::intcode::Intcode;
+
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
-fn within_beam(code: &Intcode, x: i64, y: i64) -> bool {
+fn within_beam(code: &Intcode, x: i64, y: i64) -> Result<bool, SolveError> {
     let mut cloned = code.clone();
-    cloned.run([x, y].iter().cloned());
-    cloned.next().unwrap() != 0
+    cloned
+        .run([x, y].iter().cloned())
+        .context(format!("drone failed at {}, {}", x, y))?;
+    Ok(cloned.next().context("drone reported nothing")? != 0)
 }
 
 #[derive(Debug)]
@@ -40,45 +44,60 @@ impl Beam {
         }
     }
 
-    fn within(&self, x: i64, y: i64) -> bool {
+    fn within(&self, x: i64, y: i64) -> Result<bool, SolveError> {
         within_beam(&self.code, x, y)
     }
 
-    fn find_left(&mut self) {
+    /// Moves to the left edge of the beam on the next row, returning false if cancelled first
+    fn find_left(&mut self) -> Result<bool, SolveError> {
         loop {
+            if cancel::is_cancelled() {
+                return Ok(false);
+            }
             self.y += 1;
-            let found = (self.x..self.x + self.y * 2).find(|&x| self.within(x, self.y));
-            if let Some(x) = found {
-                self.x = x;
-                break;
+            for x in self.x..self.x + self.y * 2 {
+                if self.within(x, self.y)? {
+                    self.x = x;
+                    return Ok(true);
+                }
             }
         }
     }
 
-    fn find_right(&mut self) {
+    /// Moves to the right edge of the beam on the next row, returning false if cancelled first
+    fn find_right(&mut self) -> Result<bool, SolveError> {
         loop {
+            if cancel::is_cancelled() {
+                return Ok(false);
+            }
             self.y += 1;
-            let found = (self.x..self.x + self.y * 2)
-                .skip_while(|&x| !self.within(x, self.y))
-                .find(|&x| !self.within(x, self.y));
-            if let Some(x) = found {
-                self.x = x - 1;
-                break;
+            let mut inside = false;
+            for x in self.x..self.x + self.y * 2 {
+                let within = self.within(x, self.y)?;
+                if inside && !within {
+                    self.x = x - 1;
+                    return Ok(true);
+                }
+                inside |= within;
             }
         }
     }
 }
 
 impl Iterator for Beam {
-    type Item = (i64, i64);
+    type Item = Result<(i64, i64), SolveError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let c = (self.x, self.y);
-        match self.side {
+        let found = match self.side {
             Side::Left => self.find_left(),
             Side::Right => self.find_right(),
         };
-        Some(c)
+        match found {
+            Ok(true) => Some(Ok(c)),
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        }
     }
 }
 
@@ -95,26 +114,32 @@ impl Row {
     }
 }
 
-pub fn part_a() -> String {
-    let program = setup_intcode();
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let program = parse(data)?;
     let size: i64 = 50;
-    (0..size)
+    let points = (0..size)
         .flat_map(|y| (0..size).map(move |x| (x, y)))
-        .filter(|&(x, y)| within_beam(&program, x, y))
-        .count()
-        .to_string()
+        .map(|(x, y)| within_beam(&program, x, y));
+    let count = process_results(points, |p| p.filter(|&within| within).count())?;
+    Ok(count.to_string())
 }
 
-pub fn part_b() -> String {
+pub fn part_b(data: &str) -> Result<String, SolveError> {
     let size = 100usize;
-    let program = setup_intcode();
+    let program = parse(data)?;
     let mut queue = VecDeque::<Row>::with_capacity(size);
 
     let left = Beam::new(program.clone(), Side::Left);
     let right = Beam::new(program.clone(), Side::Right);
 
     for (l, r) in left.zip(right) {
-        assert_eq!(l.1, r.1);
+        let (l, r) = (l?, r?);
+        if l.1 != r.1 {
+            return Err(SolveError::new(format!(
+                "edges of the beam were found on different rows, {} and {}",
+                l.1, r.1
+            )));
+        }
         if queue.len() == size {
             queue.pop_back();
         }
@@ -126,10 +151,16 @@ pub fn part_b() -> String {
 
         let top = queue.back().unwrap();
         let bottom = queue.front().unwrap();
-        assert_eq!(bottom.y - top.y + 1, size as i64);
+        if bottom.y - top.y + 1 != size as i64 {
+            return Err(SolveError::new(format!(
+                "rows {} to {} of the beam are not consecutive",
+                top.y, bottom.y
+            )));
+        }
         if top.x1 - bottom.x0 + 1 >= size as i64 {
-            return (bottom.x0 * 10000 + top.y).to_string();
+            return Ok((bottom.x0 * 10000 + top.y).to_string());
         }
     }
-    unreachable!()
+    // The beam only stops extending once the solver runs out of time
+    Err(SolveError::timed_out())
 }
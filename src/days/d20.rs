@@ -1,8 +1,13 @@
 /// Advent of Code 2019, day 20
 /// https://adventofcode.com/2019/day/20
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, VecDeque};
+use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
-use std::str::{from_utf8, FromStr};
+use std::str::FromStr;
+
+use super::{Context, SolveError};
+use crate::cancel;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 struct Portal(char, char);
@@ -25,7 +30,7 @@ enum Feature {
 }
 
 #[derive(Debug, Clone)]
-struct Map {
+pub struct Map {
     features: Vec<Feature>,
     width: usize,
     height: usize,
@@ -35,25 +40,6 @@ struct Map {
 }
 
 impl Map {
-    fn as_coords(&self, index: usize) -> (usize, usize) {
-        if index < self.features.len() {
-            (index % self.width, index / self.width)
-        } else {
-            panic!("index exceeds max length {}", self.features.len())
-        }
-    }
-
-    fn as_index(&self, coords: (usize, usize)) -> usize {
-        if coords.0 < self.width && coords.1 < self.height {
-            coords.0 + self.width * coords.1
-        } else {
-            panic!(
-                "coords exceed max width {} and height {}",
-                self.width, self.height
-            );
-        }
-    }
-
     fn check_adjacent(&self, index: usize, level: Option<usize>) -> bool {
         match self.features[index] {
             Feature::Empty | Feature::Wall => false,
@@ -65,51 +51,52 @@ impl Map {
         }
     }
 
-    fn adjacent(&self, index: usize, level: Option<usize>) -> Vec<(usize, Option<usize>)> {
-        let (x, y) = self.as_coords(index);
-        let mut adj = Vec::<(usize, Option<usize>)>::with_capacity(4);
-
-        let left = self.as_index((x - 1, y));
-        if x > 0 && self.check_adjacent(left, level) {
-            adj.push((left, level));
+    fn adjacent(
+        &self,
+        index: usize,
+        level: Option<usize>,
+    ) -> Result<Vec<(usize, Option<usize>)>, SolveError> {
+        let (x, y) = (index % self.width, index / self.width);
+        let mut neighbours = Vec::with_capacity(4);
+        if x > 0 {
+            neighbours.push(index - 1);
         }
-
-        let right = self.as_index((x + 1, y));
-        if x < self.width - 1 && self.check_adjacent(right, level) {
-            adj.push((right, level));
+        if x + 1 < self.width {
+            neighbours.push(index + 1);
         }
-
-        let above = self.as_index((x, y - 1));
-        if y > 0 && self.check_adjacent(above, level) {
-            adj.push((above, level));
+        if y > 0 {
+            neighbours.push(index - self.width);
         }
-
-        let below = self.as_index((x, y + 1));
-        if y < self.height - 1 && self.check_adjacent(below, level) {
-            adj.push((below, level));
+        if y + 1 < self.height {
+            neighbours.push(index + self.width);
         }
+        let mut adj = neighbours
+            .into_iter()
+            .filter(|&i| self.check_adjacent(i, level))
+            .map(|i| (i, level))
+            .collect::<Vec<(usize, Option<usize>)>>();
 
         match self.features[index] {
             Feature::Portal(p, d) if p != ENTRY && p != EXIT => {
-                let other = match self.portals[&p] {
-                    (a, b) if a == index => b,
-                    (a, b) if b == index => a,
-                    _ => panic!("portals do not match"),
+                let other = match self.portals.get(&p) {
+                    Some(&(a, b)) if a == index => b,
+                    Some(&(a, b)) if b == index => a,
+                    _ => return Err(SolveError::new(format!("portal {:?} has no pair", p))),
                 };
-                let coords = self.as_coords(other);
-                let new_level = match d {
-                    Direction::Inward => level.map(|l| l + 1),
-                    Direction::Outward => level.map(|l| l - 1),
+                let new_level = match (d, level) {
+                    (Direction::Inward, l) => l.map(|l| l + 1),
+                    (Direction::Outward, Some(0)) => return Ok(adj),
+                    (Direction::Outward, l) => l.map(|l| l - 1),
                 };
-                adj.push((self.as_index(coords), new_level));
+                adj.push((other, new_level));
             }
             _ => (),
         };
 
-        adj
+        Ok(adj)
     }
 
-    fn walk_through(&self, recursive: bool) -> usize {
+    fn walk_through(&self, recursive: bool) -> Result<usize, SolveError> {
         let start = match recursive {
             true => Some(0),
             false => None,
@@ -120,12 +107,13 @@ impl Map {
         queue.push_front((start, 0, self.entry, self.entry));
 
         while let Some((level, dist, previous, current)) = queue.pop_back() {
+            cancel::check()?;
             if current == self.exit {
                 break;
             }
-            let adjacent = self.adjacent(current, level);
+            let adjacent = self.adjacent(current, level)?;
             for &(i, ol) in adjacent.iter().filter(|&&(i, _)| i != previous) {
-                if distances.get(&(ol, i)).map_or(true, |&d| dist < d) {
+                if distances.get(&(ol, i)).is_none_or(|&d| dist < d) {
                     distances.insert((ol, i), dist + 1);
                     queue.push_front((ol, dist + 1, current, i));
                 }
@@ -133,70 +121,85 @@ impl Map {
         }
         distances
             .get(&(start, self.exit))
-            .expect("exit not found")
-            .clone()
+            .copied()
+            .context("exit not found")
     }
 }
 
 #[derive(Debug)]
-struct ParseMapError;
+pub struct ParseMapError(String);
+
+impl ParseMapError {
+    fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
 
 impl Display for ParseMapError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "reading map failed")
+        write!(f, "reading map failed: {}", self.0)
     }
 }
 
+impl Error for ParseMapError {}
+
 fn within_half(value: usize, range: usize) -> bool {
     value >= range / 4 && value < 3 * range / 4
 }
 
-fn find_portal(chars: &Vec<char>, width: usize, height: usize, index: usize) -> Feature {
-    assert_eq!(chars[index], '.');
-    let mut new_portal = None as Option<Portal>;
-    let a;
-    let b;
+/// Works out whether a path tile is next to a portal label, which is two letters read from left
+/// to right or top to bottom
+fn find_portal(
+    chars: &[char],
+    width: usize,
+    height: usize,
+    index: usize,
+) -> Result<Feature, ParseMapError> {
+    let (x, y) = ((index % width) as isize, (index / width) as isize);
+    let at = |dx: isize, dy: isize| -> Option<char> {
+        let (x, y) = (x + dx, y + dy);
+        if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+            return None;
+        }
+        Some(chars[x as usize + y as usize * width])
+    };
+    let letter = |dx, dy| at(dx, dy).filter(char::is_ascii_uppercase);
 
     let direction = match within_half(index % width, width) && within_half(index / width, height) {
         true => Direction::Inward,
         false => Direction::Outward,
     };
 
-    if chars[index - 1].is_ascii_uppercase() {
-        a = chars[index - 2];
-        b = chars[index - 1];
-        assert!(a.is_ascii_uppercase());
-        new_portal = Some(Portal(a, b));
-    } else if chars[index + 1].is_ascii_uppercase() {
-        a = chars[index + 1];
-        b = chars[index + 2];
-        assert!(b.is_ascii_uppercase());
-        new_portal = Some(Portal(a, b));
-    } else if chars[index - width].is_ascii_uppercase() {
-        a = chars[index - 2 * width];
-        b = chars[index - width];
-        assert!(a.is_ascii_uppercase());
-        new_portal = Some(Portal(a, b));
-    } else if chars[index + width].is_ascii_uppercase() {
-        a = chars[index + width];
-        b = chars[index + 2 * width];
-        assert!(b.is_ascii_uppercase());
-        new_portal = Some(Portal(a, b));
-    }
-
-    match new_portal {
-        Some(portal) => Feature::Portal(portal, direction),
-        None => Feature::Path,
+    // Offsets of the label's first and second letters
+    let labels = [
+        ((-2, 0), (-1, 0)),
+        ((1, 0), (2, 0)),
+        ((0, -2), (0, -1)),
+        ((0, 1), (0, 2)),
+    ];
+    for &((ax, ay), (bx, by)) in labels.iter() {
+        let next_to = if ax < 0 || ay < 0 { (bx, by) } else { (ax, ay) };
+        if letter(next_to.0, next_to.1).is_none() {
+            continue;
+        }
+        return match (letter(ax, ay), letter(bx, by)) {
+            (Some(a), Some(b)) => Ok(Feature::Portal(Portal(a, b), direction)),
+            _ => Err(ParseMapError::new(format!(
+                "portal label next to ({}, {}) is only one letter",
+                x, y
+            ))),
+        };
     }
+    Ok(Feature::Path)
 }
 
 impl FromStr for Map {
     type Err = ParseMapError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = s.lines().next().unwrap().len();
-        if !s.lines().skip(1).all(|line| line.len() == width) {
-            panic!("not all lines are the same width");
+        let width = s.lines().next().map_or(0, |line| line.chars().count());
+        if !s.lines().skip(1).all(|line| line.chars().count() == width) {
+            return Err(ParseMapError::new("not all lines are the same width"));
         }
         let height = s.lines().count();
 
@@ -209,18 +212,21 @@ impl FromStr for Map {
             .iter()
             .enumerate()
             .map(|(i, &c)| match c {
-                ' ' => Feature::Empty,
-                '#' => Feature::Wall,
+                ' ' => Ok(Feature::Empty),
+                '#' => Ok(Feature::Wall),
                 '.' => find_portal(&chars, width, height, i),
-                c if c.is_ascii_uppercase() => Feature::Empty,
-                c => panic!("unrecognised character: {:?}", c),
+                c if c.is_ascii_uppercase() => Ok(Feature::Empty),
+                c => Err(ParseMapError::new(format!(
+                    "unrecognised character: {:?}",
+                    c
+                ))),
             })
-            .collect::<Vec<Feature>>();
+            .collect::<Result<Vec<Feature>, _>>()?;
 
         let portals_found = features
             .iter()
             .enumerate()
-            .filter_map(|(i, f)| match f.clone() {
+            .filter_map(|(i, f)| match *f {
                 Feature::Portal(p, _) => Some((i, p)),
                 _ => None,
             })
@@ -233,23 +239,23 @@ impl FromStr for Map {
         for &(i, p) in portals_found.iter() {
             if p == ENTRY {
                 match entry {
-                    Some(_) => panic!("more than one entrance"),
+                    Some(_) => return Err(ParseMapError::new("more than one entrance")),
                     None => entry = Some(i),
                 }
             } else if p == EXIT {
                 match exit {
-                    Some(_) => panic!("more than one exit"),
+                    Some(_) => return Err(ParseMapError::new("more than one exit")),
                     None => exit = Some(i),
                 }
-            } else if !portals.contains_key(&p) {
+            } else if let Entry::Vacant(e) = portals.entry(p) {
                 let others = portals_found
                     .iter()
                     .filter_map(|&(i, q)| if p == q { Some(i) } else { None })
                     .collect::<Vec<usize>>();
                 if others.len() != 2 {
-                    panic!("number of portals must be 2");
+                    return Err(ParseMapError::new("number of portals must be 2"));
                 }
-                portals.insert(p, (others[0], others[1]));
+                e.insert((others[0], others[1]));
             }
         }
 
@@ -258,23 +264,70 @@ impl FromStr for Map {
             height,
             width,
             portals,
-            entry: entry.expect("no entrance found"),
-            exit: exit.expect("no exit found"),
+            entry: entry.ok_or_else(|| ParseMapError::new("no entrance found"))?,
+            exit: exit.ok_or_else(|| ParseMapError::new("no exit found"))?,
         })
     }
 }
 
-fn read_map() -> Map {
-    let data = include_bytes!("input/d20.txt");
-    from_utf8(data).unwrap().parse().unwrap()
+pub fn parse(data: &str) -> Result<Map, SolveError> {
+    data.parse().context("invalid map")
+}
+
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let map = parse(data)?;
+    let distance = map.walk_through(false)?;
+    Ok(distance.to_string())
 }
 
-pub fn part_a() -> String {
-    let map = read_map();
-    map.walk_through(false).to_string()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    let map = parse(data)?;
+    let distance = map.walk_through(true)?;
+    Ok(distance.to_string())
 }
 
-pub fn part_b() -> String {
-    let map = read_map();
-    map.walk_through(true).to_string()
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "         A           
+         A           
+  #######.#########  
+  #######.........#  
+  #######.#######.#  
+  #######.#######.#  
+  #######.#######.#  
+  #####  B    ###.#  
+BC...##  C    ###.#  
+  ##.##       ###.#  
+  ##...DE  F  ###.#  
+  #####    G  ###.#  
+  #########.#####.#  
+DE..#######...###.#  
+  #.#########.###.#  
+FG..#########.....#  
+  ###########.#####  
+             Z       
+             Z       ";
+
+    #[test]
+    fn test_walk_through() {
+        let map = EXAMPLE.parse::<Map>().unwrap();
+        assert_eq!(map.walk_through(false).unwrap(), 23);
+        assert_eq!(map.walk_through(true).unwrap(), 26);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let error = |map: &str| map.parse::<Map>().unwrap_err().to_string();
+        assert_eq!(
+            error(".A\n.."),
+            "reading map failed: portal label next to (0, 0) is only one letter"
+        );
+        assert_eq!(
+            error("#..\nA.#\n#.B"),
+            "reading map failed: portal label next to (1, 1) is only one letter"
+        );
+        assert_eq!(error("AA.##"), "reading map failed: no exit found");
+    }
 }
@@ -1,15 +1,15 @@
 /// Advent of Code 2019, day 21
 /// https://adventofcode.com/2019/day/21
-use std::fmt::{Display, Formatter, Result as FmtResult, Write};
-use std::str::{from_utf8, FromStr};
-use std::u8;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
-use super::intcode::Intcode;
-use std::convert::TryInto;
+use super::{Context, SolveError};
+use crate::intcode::ascii::AsciiIntcode;
+use crate::intcode::Intcode;
 
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d21.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
 const REGISTERS: [char; 11] = ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'T'];
@@ -19,10 +19,7 @@ struct R(char);
 
 impl R {
     fn writeable(&self) -> bool {
-        match self.0 {
-            'J' | 'T' => true,
-            _ => false,
-        }
+        matches!(self.0, 'J' | 'T')
     }
 
     fn char(self) -> char {
@@ -62,6 +59,8 @@ impl Display for ParseInstructionError {
     }
 }
 
+impl Error for ParseInstructionError {}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Instruction {
     And(R, R),
@@ -86,8 +85,8 @@ impl Display for Instruction {
 fn word(s: &str) -> Result<(&str, &str), ()> {
     let start = s.trim_start();
     match start.chars().position(|c| c.is_whitespace()) {
-        Some(w) if w == 0 => Err(()),
-        Some(w) => Ok((&start[w..].trim_start(), &start[..w])),
+        Some(0) => Err(()),
+        Some(w) => Ok((start[w..].trim_start(), &start[..w])),
         None => Ok(("", start)),
     }
 }
@@ -132,39 +131,34 @@ fn parse_instructions(s: &str) -> Result<Vec<Instruction>, ParseInstructionError
     Ok(ins)
 }
 
-fn execute_instructions(code: &mut Intcode, instructions: &[Instruction]) -> Option<i64> {
-    let mut string = String::new();
+fn execute_instructions(code: Intcode, instructions: &[Instruction]) -> Result<i64, SolveError> {
+    let mut code = AsciiIntcode::new(code);
     for i in instructions {
-        write!(string, "{}\n", i).unwrap();
-    }
-    code.run(string.bytes().map(|b| b as i64));
-
-    let mut bytes = Vec::<u8>::new();
-    let mut result = Vec::<i64>::new();
-    for i in code {
-        match i.try_into() {
-            Ok(b) => bytes.push(b),
-            Err(_) => result.push(i),
-        }
+        code.send_line(&i.to_string())
+            .context("invalid springscript")?;
     }
-    if !result.is_empty() {
-        Some(result[0])
-    } else {
-        eprintln!("{}", from_utf8(&bytes).unwrap());
-        None
+    code.run().context("Intcode program failed")?;
+
+    let damage = code.results().next();
+    match damage {
+        Some(damage) => Ok(damage),
+        None => Err(SolveError::new(format!(
+            "springdroid fell into space:\n{}",
+            code.take_text().trim_end()
+        ))),
     }
 }
 
 macro_rules! execute {
-    ($($t:tt)*) => {{
-        let parsed = parse_instructions(stringify!($($t)*)).unwrap();
-        let mut program = setup_intcode();
-        execute_instructions(&mut program, &parsed).unwrap()
+    ($data:expr; $($t:tt)*) => {{
+        let parsed = parse_instructions(stringify!($($t)*)).context("invalid springscript")?;
+        execute_instructions(parse($data)?, &parsed)?
     }}
 }
 
-pub fn part_a() -> String {
+pub fn part_a(data: &str) -> Result<String, SolveError> {
     let result = execute! {
+        data;
         NOT J T // set T to true
         AND A T
         AND B T
@@ -173,11 +167,12 @@ pub fn part_a() -> String {
         AND D J // to jump 4th square must be true and any of 1-3 squares false
         WALK
     };
-    result.to_string()
+    Ok(result.to_string())
 }
 
-pub fn part_b() -> String {
+pub fn part_b(data: &str) -> Result<String, SolveError> {
     let result = execute! {
+        data;
         NOT J T
         AND A T
         AND B T
@@ -190,5 +185,5 @@ pub fn part_b() -> String {
         AND T J
         RUN
     };
-    result.to_string()
+    Ok(result.to_string())
 }
@@ -2,7 +2,9 @@
 /// https://adventofcode.com/2019/day/22
 use std::fmt::{Debug, Formatter, Result as FmtResult};
 use std::ops::{Add, Div, Mul, Neg, Sub};
-use std::str::{from_utf8, FromStr};
+use std::str::FromStr;
+
+use super::{Context, SolveError};
 
 // https://en.wikipedia.org/wiki/Extended_Euclidean_algorithm
 fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
@@ -45,7 +47,10 @@ impl Mod {
 
     fn check(&self, other: Self) {
         if self.modulus != other.modulus {
-            panic!("moduli expected to be equal, got {} != {}",)
+            panic!(
+                "moduli expected to be equal, got {} != {}",
+                self.modulus, other.modulus
+            )
         }
     }
 
@@ -250,7 +255,7 @@ impl ModLinearExpression {
         if depth == 0 {
             return Self::base(modulus);
         } else if depth == 1 {
-            return self.clone();
+            return *self;
         }
 
         let pow_a = self.a.pow(depth);
@@ -268,7 +273,7 @@ impl ModLinearExpression {
 }
 
 #[derive(Debug, Copy, Clone)]
-enum Instruction {
+pub enum Instruction {
     Stack,
     Cut(i64),
     Deal(i64),
@@ -285,36 +290,38 @@ impl Instruction {
 }
 
 impl FromStr for Instruction {
-    type Err = ();
+    type Err = SolveError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const STACK: &'static str = "deal into new stack";
-        const CUT: &'static str = r"cut ";
-        const INCREMENT: &'static str = "deal with increment ";
+        const STACK: &str = "deal into new stack";
+        const CUT: &str = r"cut ";
+        const INCREMENT: &str = "deal with increment ";
 
         let i = if s == STACK {
             Instruction::Stack
         } else if s.starts_with(CUT) {
-            let n = s.trim_start_matches(CUT).parse::<i64>().unwrap();
+            let n = s
+                .trim_start_matches(CUT)
+                .parse::<i64>()
+                .context("invalid cut")?;
             Instruction::Cut(n)
         } else if s.starts_with(INCREMENT) {
-            let n = s.trim_start_matches(INCREMENT).parse::<i64>().unwrap();
+            let n = s
+                .trim_start_matches(INCREMENT)
+                .parse::<i64>()
+                .context("invalid increment")?;
             Instruction::Deal(n)
         } else {
-            Err(())?
+            return Err(SolveError::new(format!("unrecognised technique: {:?}", s)));
         };
         Ok(i)
     }
 }
 
-fn read_instructions() -> Vec<Instruction> {
-    let data = include_bytes!("input/d22.txt");
-    from_utf8(data)
-        .unwrap()
-        .lines()
+pub fn parse(data: &str) -> Result<Vec<Instruction>, SolveError> {
+    data.lines()
         .map(|line| line.parse::<Instruction>())
-        .collect::<Result<Vec<Instruction>, ()>>()
-        .unwrap()
+        .collect::<Result<Vec<Instruction>, _>>()
 }
 
 fn compose_instructions(m: i64, ins: &[Instruction]) -> ModLinearExpression {
@@ -323,22 +330,22 @@ fn compose_instructions(m: i64, ins: &[Instruction]) -> ModLinearExpression {
     })
 }
 
-pub fn part_a() -> String {
+pub fn part_a(data: &str) -> Result<String, SolveError> {
     let size = 10007;
     let card = 2019;
 
-    let expression = compose_instructions(size, &read_instructions());
-    expression.apply(card, false).to_string()
+    let expression = compose_instructions(size, &parse(data)?);
+    Ok(expression.apply(card, false).to_string())
 }
 
-pub fn part_b() -> String {
+pub fn part_b(data: &str) -> Result<String, SolveError> {
     let size = 119315717514047;
     let repeat = 101741582076661;
     let card = 2020;
 
-    let base = compose_instructions(size, &read_instructions());
+    let base = compose_instructions(size, &parse(data)?);
     let expression = base.compose_self(repeat);
-    expression.apply(card, true).to_string()
+    Ok(expression.apply(card, true).to_string())
 }
 
 #[cfg(test)]
@@ -351,7 +358,7 @@ mod test {
         let collect = |deck: &[i64]| {
             let mut new_deck = vec![0i64; size as usize];
             for (i, &c) in deck.iter().enumerate() {
-                assert!(c >= 0 && c < 11);
+                assert!((0..11).contains(&c));
                 new_deck[c as usize] = i as i64;
             }
             new_deck
@@ -1,99 +1,86 @@
 /// Advent of Code 2019, day 23
 /// https://adventofcode.com/2019/day/23
-use std::cell::RefCell;
-use std::collections::VecDeque;
-use std::iter::once;
-use std::str::from_utf8;
+use super::{Context, SolveError};
+use crate::intcode::network::{Action, Config, Network, NetworkError, Packet, Router};
+use crate::intcode::Intcode;
 
-use super::intcode::Intcode;
-
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d23.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
 }
 
-#[derive(Debug)]
-struct Controller {
-    program: RefCell<Intcode>,
-    incoming: RefCell<VecDeque<[i64; 2]>>,
-}
+/// Address of the NAT, which takes packets outside the network
+const NAT: i64 = 255;
 
-impl Controller {
-    fn start(base: &Intcode, number: i64) -> Self {
-        let mut code = base.clone();
-        code.run(once(number));
-        assert!(!code.finished());
-        Self {
-            program: RefCell::new(code),
-            incoming: RefCell::new(VecDeque::new()),
-        }
-    }
+const SIZE: usize = 50;
 
-    fn is_idle(&self) -> bool {
-        self.incoming.borrow().is_empty()
-    }
-
-    fn send(&self, content: [i64; 2]) {
-        self.incoming.borrow_mut().push_back(content)
-    }
-
-    fn run(&self) -> Vec<(usize, [i64; 2])> {
-        if !self.incoming.borrow().is_empty() {
-            for packet in self.incoming.borrow_mut().drain(..) {
-                self.program.borrow_mut().run(packet.iter().cloned());
-            }
-        } else {
-            self.program.borrow_mut().run(once(-1));
-        }
+/// Packets are an X and Y value, and a computer is idle once it has read `-1` twice in a row
+const CONFIG: Config = Config {
+    payload: 2,
+    idle_reads: 2,
+    log: 0,
+};
 
-        let output = self.program.borrow_mut().drain().collect::<Vec<i64>>();
-        assert_eq!(output.len() % 3, 0);
-        output
-            .chunks(3)
-            .map(|p| (p[0] as usize, [p[1], p[2]]))
-            .collect()
-    }
+/// NAT that holds the last packet it received, and passes it to controller 0 when the network is
+/// idle. Stops at the first packet it receives if `terminate` is set, or otherwise once it
+/// passes the same Y value twice in a row.
+struct Nat {
+    terminate: bool,
+    last: Option<Vec<i64>>,
+    last_sent: Option<i64>,
 }
 
-fn run_network(terminate: bool) -> i64 {
-    let size = 50usize;
-    let program = setup_intcode();
-
-    let controllers = (0..size as i64)
-        .map(|i| Controller::start(&program, i))
-        .collect::<Vec<Controller>>();
+impl Router for Nat {
+    type Output = i64;
 
-    let mut nat = None as Option<[i64; 2]>;
-    let mut last_sent = None as Option<i64>;
-
-    loop {
-        for c in controllers.iter() {
-            for (dest, content) in c.run() {
-                match dest {
-                    255 if terminate => return content[1],
-                    255 => nat = Some(content),
-                    d if d < size => controllers[d].send(content),
-                    d => panic!("invalid address {}", d),
-                };
+    fn receive(&mut self, packet: Packet) -> Action<i64> {
+        match packet.destination {
+            NAT if self.terminate => Action::Stop(packet.payload[1]),
+            NAT => {
+                self.last = Some(packet.payload);
+                Action::Wait
             }
+            _ => Action::Reject,
         }
+    }
 
-        if !terminate && controllers.iter().all(|c| c.is_idle()) {
-            let content = nat.unwrap();
-            if last_sent == Some(content[1]) {
-                return content[1];
-            } else {
-                last_sent = Some(content[1]);
-            }
-            controllers[0].send(content);
+    fn idle(&mut self) -> Action<i64> {
+        let payload = match &self.last {
+            Some(payload) => payload.clone(),
+            None => return Action::Wait,
+        };
+        if self.last_sent == Some(payload[1]) {
+            return Action::Stop(payload[1]);
         }
+        self.last_sent = Some(payload[1]);
+        Action::Deliver(Packet {
+            source: None,
+            destination: 0,
+            payload,
+        })
+    }
+}
+
+fn run_network(data: &str, terminate: bool) -> Result<i64, SolveError> {
+    let mut network = Network::new(&parse(data)?, SIZE, CONFIG);
+    let mut nat = Nat {
+        terminate,
+        last: None,
+        last_sent: None,
+    };
+    match network.run(&mut nat) {
+        Ok(y) => Ok(y),
+        Err(NetworkError::Cancelled) => Err(SolveError::timed_out()),
+        Err(NetworkError::Deadlock) => Err(SolveError::new(
+            "network idle before any packet reached the NAT",
+        )),
+        Err(err) => Err(SolveError::with_cause("network failed", err)),
     }
 }
 
-pub fn part_a() -> String {
-    run_network(true).to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    Ok(run_network(data, true)?.to_string())
 }
 
-pub fn part_b() -> String {
-    run_network(false).to_string()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    Ok(run_network(data, false)?.to_string())
 }
@@ -1,7 +1,10 @@
 /// Advent of Code 2019, day 24
 /// https://adventofcode.com/2019/day/24
 use std::collections::HashSet;
-use std::str::{from_utf8, FromStr};
+use std::str::FromStr;
+
+use super::{Context, SolveError};
+use crate::cancel;
 
 const WIDTH: u32 = 5;
 const END: u32 = WIDTH - 1;
@@ -17,23 +20,30 @@ const fn as_index(x: u32, y: u32) -> u32 {
     x + y * WIDTH
 }
 
-fn read_grid() -> &'static str {
-    let data = include_bytes!("input/d24.txt");
-    from_utf8(data).unwrap()
-}
-
-fn parse_grid(s: &str) -> u32 {
+pub fn parse(s: &str) -> Result<u32, SolveError> {
     let cells = s
         .chars()
         .filter_map(|c| match c {
-            '.' | '?' => Some(0u32),
-            '#' => Some(1u32),
-            _ => None,
+            '.' | '?' => Some(Ok(0u32)),
+            '#' => Some(Ok(1u32)),
+            c if c.is_whitespace() => None,
+            c => Some(Err(SolveError::new(format!(
+                "unrecognised character: {:?}",
+                c
+            )))),
         })
+        .collect::<Result<Vec<u32>, _>>()?;
+    if cells.len() != SIZE as usize {
+        return Err(SolveError::new(format!(
+            "expected {} cells, found {}",
+            SIZE,
+            cells.len()
+        )));
+    }
+    Ok(cells
+        .iter()
         .enumerate()
-        .fold(0u32, |a, (i, b)| a | b << i as u32);
-    assert!(cells < (1 << SIZE));
-    cells
+        .fold(0u32, |a, (i, b)| a | b << i as u32))
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -77,21 +87,22 @@ impl Grid {
 }
 
 impl FromStr for Grid {
-    type Err = ();
+    type Err = SolveError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(parse_grid(s)))
+        Ok(Self(parse(s)?))
     }
 }
 
-fn run_grid() -> u32 {
+fn run_grid(data: &str) -> Result<u32, SolveError> {
     let mut v;
-    let mut grid = read_grid().parse::<Grid>().unwrap();
+    let mut grid = data.parse::<Grid>().context("invalid grid")?;
     let mut set = HashSet::<u32>::new();
     loop {
+        cancel::check()?;
         v = grid.as_u32();
         if !set.insert(v) {
-            break v;
+            break Ok(v);
         }
         grid.update();
     }
@@ -215,27 +226,27 @@ impl RecursiveGrid {
 }
 
 impl FromStr for RecursiveGrid {
-    type Err = ();
+    type Err = SolveError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(vec![parse_grid(s)]))
+        Ok(Self(vec![parse(s)?]))
     }
 }
 
-fn run_recursive_grid() -> usize {
-    let mut grid = read_grid().parse::<RecursiveGrid>().unwrap();
+fn run_recursive_grid(data: &str) -> Result<usize, SolveError> {
+    let mut grid = data.parse::<RecursiveGrid>().context("invalid grid")?;
     for _ in 0..200 {
         grid.update();
     }
-    grid.count()
+    Ok(grid.count())
 }
 
-pub fn part_a() -> String {
-    run_grid().to_string()
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    Ok(run_grid(data)?.to_string())
 }
 
-pub fn part_b() -> String {
-    run_recursive_grid().to_string()
+pub fn part_b(data: &str) -> Result<String, SolveError> {
+    Ok(run_recursive_grid(data)?.to_string())
 }
 
 #[cfg(test)]
@@ -1,43 +1,224 @@
 /// Advent of Code 2019, day 23
 /// https://adventofcode.com/2019/day/23
-use std::io::Result as IoResult;
-use std::str::from_utf8;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Result as IoResult, Write};
 
-use super::intcode::Intcode;
-use std::io::{stdin, stdout, Write};
+use regex::Regex;
 
-fn setup_intcode() -> Intcode {
-    let data = include_bytes!("input/d25.txt");
-    from_utf8(data).unwrap().parse::<Intcode>().unwrap()
-}
+use super::{Context, SolveError};
+use crate::intcode::ascii::AsciiIntcode;
+use crate::intcode::{Intcode, RunState};
 
-fn run_game() -> IoResult<()> {
-    let mut code = setup_intcode();
+pub fn parse(data: &str) -> Result<Intcode, SolveError> {
+    data.parse::<Intcode>().context("invalid Intcode program")
+}
 
-    let input = stdin();
+/// Plays the game, reading commands from the input and writing the game's text to the output.
+/// Typing `save FILE` or `load FILE` checkpoints the game or resumes it from a snapshot.
+pub fn play(program: Intcode, mut input: impl BufRead, mut output: impl Write) -> IoResult<()> {
+    let mut code = AsciiIntcode::new(program);
     let mut incoming = String::new();
-
-    let mut output = stdout();
-    let mut bytes = Vec::<u8>::new();
     loop {
-        code.run(incoming.chars().map(|c| c as u8 as i64));
-        bytes.extend(code.drain().map(|c| c as u8));
-        incoming.clear();
-
-        writeln!(output, "{}", from_utf8(&bytes).unwrap())?;
+        let state = code
+            .run()
+            .map_err(|err| IoError::new(ErrorKind::InvalidData, err))?;
+        writeln!(output, "{}", code.take_text())?;
         output.flush()?;
-        bytes.clear();
 
-        if code.finished() {
+        if state == RunState::Halted {
             break;
         }
-        input.read_line(&mut incoming)?;
+        incoming.clear();
+        if input.read_line(&mut incoming)? == 0 {
+            break;
+        }
+        let line = incoming.trim_end();
+        match line.split_once(' ') {
+            Some(("save", path)) => {
+                code.program().save(File::create(path)?)?;
+                writeln!(output, "Saved to {}", path)?;
+            }
+            Some(("load", path)) => {
+                *code.program_mut() = Intcode::load(BufReader::new(File::open(path)?))?;
+                writeln!(output, "Loaded from {}", path)?;
+            }
+            _ => {
+                if let Err(err) = code.send_line(line) {
+                    writeln!(output, "{}", err)?;
+                }
+            }
+        }
     }
 
     Ok(())
 }
 
-pub fn part_a() -> String {
-    // run_game().unwrap();
-    8462464.to_string()
+/// Instructions the game may take to respond to a command before it is taken to be stuck
+const BUDGET: usize = 1_000_000;
+
+/// Room the game describes after a command
+#[derive(Debug)]
+struct Room {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
+}
+
+impl Room {
+    /// Reads the last room described in the text, if any
+    fn parse(text: &str) -> Option<Self> {
+        let start = text.rfind("== ")?;
+        let mut lines = text[start..].lines();
+        let name = lines.next()?.trim_matches(|c| c == '=' || c == ' ');
+        let mut room = Room {
+            name: name.to_string(),
+            doors: Vec::new(),
+            items: Vec::new(),
+        };
+        let mut list = None;
+        for line in lines {
+            match line {
+                "Doors here lead:" => list = Some(&mut room.doors),
+                "Items here:" => list = Some(&mut room.items),
+                _ => match (line.strip_prefix("- "), list.as_mut()) {
+                    (Some(entry), Some(list)) => list.push(entry.to_string()),
+                    _ => list = None,
+                },
+            }
+        }
+        Some(room)
+    }
+}
+
+fn opposite(door: &str) -> &str {
+    match door {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        _ => "east",
+    }
+}
+
+/// Droid exploring the ship, picking up everything it can carry
+struct Droid {
+    game: AsciiIntcode,
+    items: Vec<String>,
+    /// Doors from the start to the room next to the pressure-sensitive floor, then the door to
+    /// the floor
+    checkpoint: Option<(Vec<String>, String)>,
+}
+
+impl Droid {
+    /// Sends a command to a game, returning the text it responds with. Fails if it gets stuck.
+    fn send(game: &mut AsciiIntcode, command: &str) -> Result<(String, RunState), SolveError> {
+        game.send_line(command).context("invalid command")?;
+        let state = game
+            .run_with_budget(BUDGET)
+            .context("Intcode program failed")?;
+        Ok((game.take_text(), state))
+    }
+
+    fn command(&mut self, command: &str) -> Result<String, SolveError> {
+        match Self::send(&mut self.game, command)? {
+            (text, RunState::AwaitingInput) => Ok(text),
+            (text, state) => Err(SolveError::new(format!(
+                "game stopped with {:?} after {:?}: {}",
+                state, command, text
+            ))),
+        }
+    }
+
+    /// Whether the droid can still move after taking the item, tried on a copy of the game
+    fn is_safe(&self, item: &str, door: &str) -> Result<bool, SolveError> {
+        let mut game = self.game.clone();
+        if Self::send(&mut game, &format!("take {}", item))?.1 != RunState::AwaitingInput {
+            return Ok(false);
+        }
+        let (text, state) = Self::send(&mut game, door)?;
+        Ok(state == RunState::AwaitingInput && Room::parse(&text).is_some())
+    }
+
+    /// Visits every room reachable from this one, returning to it afterwards
+    fn explore(&mut self, room: Room, path: &mut Vec<String>) -> Result<(), SolveError> {
+        let back = path.last().map(|d| opposite(d).to_string());
+        for item in &room.items {
+            if self.is_safe(item, &room.doors[0])? {
+                self.command(&format!("take {}", item))?;
+                self.items.push(item.clone());
+            }
+        }
+        for door in &room.doors {
+            if Some(door) == back.as_ref() {
+                continue;
+            }
+            let text = self.command(door)?;
+            let next =
+                Room::parse(&text).context(format!("no room described after going {}", door))?;
+            // The floor sends the droid straight back if it weighs the wrong amount
+            if next.name == room.name {
+                self.checkpoint = Some((path.clone(), door.clone()));
+                continue;
+            }
+            path.push(door.clone());
+            self.explore(next, path)?;
+            path.pop();
+            self.command(opposite(door))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn part_a(data: &str) -> Result<String, SolveError> {
+    let mut game = AsciiIntcode::new(parse(data)?);
+    game.run().context("Intcode program failed")?;
+    let start = Room::parse(&game.take_text()).context("game didn't describe a room")?;
+    let mut droid = Droid {
+        game,
+        items: Vec::new(),
+        checkpoint: None,
+    };
+    droid.explore(start, &mut Vec::new())?;
+    let (path, floor) = droid
+        .checkpoint
+        .take()
+        .context("no pressure-sensitive floor found")?;
+    for door in &path {
+        droid.command(door)?;
+    }
+
+    // Try every set of items, changing one item at a time
+    let password = Regex::new(r"(\d+) on the keypad").unwrap();
+    let items = droid.items.clone();
+    for i in 0..1_u32 << items.len() {
+        if i > 0 {
+            let changed = i.trailing_zeros() as usize;
+            let held = (i ^ (i >> 1)) & (1 << changed) == 0;
+            let action = if held { "take" } else { "drop" };
+            droid.command(&format!("{} {}", action, items[changed]))?;
+        }
+        let (text, _) = Droid::send(&mut droid.game, &floor)?;
+        if let Some(captures) = password.captures(&text) {
+            return Ok(captures[1].to_string());
+        }
+    }
+    Err(SolveError::new(format!(
+        "no set of the items {} gets past the pressure-sensitive floor",
+        items.join(", ")
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::INPUTS;
+
+    #[test]
+    fn test_play() {
+        let mut output = Vec::new();
+        let script = "inv\nfly\n";
+        play(parse(INPUTS[24]).unwrap(), script.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("You aren't carrying any items."));
+        assert!(output.ends_with("Command?\n\n"));
+    }
 }
@@ -0,0 +1,107 @@
+/// Errors returned by solvers
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+type Cause = Box<dyn Error + Send + Sync + 'static>;
+
+/// An error raised while solving a part, with the day and part it came from and an optional cause
+#[derive(Debug)]
+pub struct SolveError {
+    day: Option<usize>,
+    part: Option<usize>,
+    message: String,
+    cause: Option<Cause>,
+    timed_out: bool,
+}
+
+impl SolveError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            day: None,
+            part: None,
+            message: message.into(),
+            cause: None,
+            timed_out: false,
+        }
+    }
+
+    /// An error for a solver stopped because it ran past its deadline
+    pub fn timed_out() -> Self {
+        Self {
+            timed_out: true,
+            ..Self::new("timed out")
+        }
+    }
+
+    pub fn with_cause(message: impl Into<String>, cause: impl Into<Cause>) -> Self {
+        Self {
+            cause: Some(cause.into()),
+            ..Self::new(message)
+        }
+    }
+
+    /// Marks the error with the day and part being solved when it was raised
+    pub fn at(self, day: usize, part: usize) -> Self {
+        Self {
+            day: Some(day),
+            part: Some(part),
+            ..self
+        }
+    }
+
+    pub fn day(&self) -> Option<usize> {
+        self.day
+    }
+
+    pub fn part(&self) -> Option<usize> {
+        self.part
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Iterates over the messages of this error followed by each of its causes in turn
+    pub fn chain(&self) -> impl Iterator<Item = String> + '_ {
+        let mut next: Option<&(dyn Error + 'static)> = Some(self);
+        std::iter::from_fn(move || {
+            let current = next?;
+            next = current.source();
+            Some(current.to_string())
+        })
+    }
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for SolveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause
+            .as_ref()
+            .map(|c| c.as_ref() as &(dyn Error + 'static))
+    }
+}
+
+/// Adds a message to a failed result or an empty option, turning it into a `SolveError`
+pub trait Context<T> {
+    fn context(self, message: impl Into<String>) -> Result<T, SolveError>;
+}
+
+impl<T, E> Context<T> for Result<T, E>
+where
+    E: Into<Cause>,
+{
+    fn context(self, message: impl Into<String>) -> Result<T, SolveError> {
+        self.map_err(|err| SolveError::with_cause(message, err))
+    }
+}
+
+impl<T> Context<T> for Option<T> {
+    fn context(self, message: impl Into<String>) -> Result<T, SolveError> {
+        self.ok_or_else(|| SolveError::new(message))
+    }
+}
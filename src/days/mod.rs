@@ -1,60 +1,264 @@
 /// Collect all problems into a list to be iterated over
-mod d01;
-mod d02;
-mod d03;
-mod d04;
-mod d05;
-mod d06;
-mod d07;
-mod d08;
-mod d09;
-mod d10;
-mod d11;
-mod d12;
-mod d13;
-mod d14;
-mod d15;
-mod d16;
-mod d17;
-mod d18;
-mod d19;
-mod d20;
-mod d21;
-mod d22;
-mod d23;
-mod d24;
-mod d25;
-
-mod intcode;
-
-pub type Solver = dyn Fn() -> String + Send + Sync + 'static;
+pub mod d01;
+pub mod d02;
+pub mod d03;
+pub mod d04;
+pub mod d05;
+pub mod d06;
+pub mod d07;
+pub mod d08;
+pub mod d09;
+pub mod d10;
+pub mod d11;
+pub mod d12;
+pub mod d13;
+pub mod d14;
+pub mod d15;
+pub mod d16;
+pub mod d17;
+pub mod d18;
+pub mod d19;
+pub mod d20;
+pub mod d21;
+pub mod d22;
+pub mod d23;
+pub mod d24;
+pub mod d25;
+
+mod error;
+
+pub use error::{Context, SolveError};
+
+pub type Solver = dyn Fn(&str) -> Result<String, SolveError> + Send + Sync + 'static;
+
+/// Puzzle inputs embedded in the binary, used when no other input is given for a day
+pub const INPUTS: [&str; 25] = [
+    include_str!("input/d01.txt"),
+    include_str!("input/d02.txt"),
+    include_str!("input/d03.txt"),
+    include_str!("input/d04.txt"),
+    include_str!("input/d05.txt"),
+    include_str!("input/d06.txt"),
+    include_str!("input/d07.txt"),
+    include_str!("input/d08.txt"),
+    include_str!("input/d09.txt"),
+    include_str!("input/d10.txt"),
+    include_str!("input/d11.txt"),
+    include_str!("input/d12.txt"),
+    include_str!("input/d13.txt"),
+    include_str!("input/d14.txt"),
+    include_str!("input/d15.txt"),
+    include_str!("input/d16.txt"),
+    include_str!("input/d17.txt"),
+    include_str!("input/d18.txt"),
+    include_str!("input/d19.txt"),
+    include_str!("input/d20.txt"),
+    include_str!("input/d21.txt"),
+    include_str!("input/d22.txt"),
+    include_str!("input/d23.txt"),
+    include_str!("input/d24.txt"),
+    include_str!("input/d25.txt"),
+];
+
+/// Description of the solver for one part of a day's puzzle
+#[derive(Clone)]
+pub struct Descriptor {
+    pub day: usize,
+    pub part: usize,
+    pub title: &'static str,
+    pub tags: &'static [&'static str],
+    /// Name of the input file for the day, as looked for in an input directory
+    pub input: String,
+    pub solver: &'static Solver,
+}
+
+impl Descriptor {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(&tag)
+    }
+}
+
+fn day(
+    day: usize,
+    title: &'static str,
+    tags: &'static [&'static str],
+    solvers: &[&'static Solver],
+) -> Vec<Descriptor> {
+    solvers
+        .iter()
+        .enumerate()
+        .map(|(i, &solver)| Descriptor {
+            day,
+            part: i + 1,
+            title,
+            tags,
+            input: format!("d{:02}.txt", day),
+            solver,
+        })
+        .collect()
+}
 
 lazy_static! {
-    pub static ref SOLVERS: Vec<Vec<&'static Solver>> = vec![
-        vec![&d01::part_a, &d01::part_b],
-        vec![&d02::part_a, &d02::part_b],
-        vec![&d03::part_a, &d03::part_b],
-        vec![&d04::part_a, &d04::part_b],
-        vec![&d05::part_a, &d05::part_b],
-        vec![&d06::part_a, &d06::part_b],
-        vec![&d07::part_a, &d07::part_b],
-        vec![&d08::part_a, &d08::part_b],
-        vec![&d09::part_a, &d09::part_b],
-        vec![&d10::part_a, &d10::part_b],
-        vec![&d11::part_a, &d11::part_b],
-        vec![&d12::part_a, &d12::part_b],
-        vec![&d13::part_a, &d13::part_b],
-        vec![&d14::part_a, &d14::part_b],
-        vec![&d15::part_a, &d15::part_b],
-        vec![&d16::part_a, &d16::part_b],
-        vec![&d17::part_a, &d17::part_b],
-        vec![&d18::part_a, &d18::part_b],
-        vec![&d19::part_a, &d19::part_b],
-        vec![&d20::part_a, &d20::part_b],
-        vec![&d21::part_a, &d21::part_b],
-        vec![&d22::part_a, &d22::part_b],
-        vec![&d23::part_a, &d23::part_b],
-        vec![&d24::part_a, &d24::part_b],
-        vec![&d25::part_a],
-    ];
+    /// Every solver in order of day and part
+    pub static ref SOLVERS: Vec<Descriptor> = [
+        day(
+            1,
+            "The Tyranny of the Rocket Equation",
+            &["arithmetic"],
+            &[&d01::part_a, &d01::part_b],
+        ),
+        day(
+            2,
+            "1202 Program Alarm",
+            &["intcode"],
+            &[&d02::part_a, &d02::part_b],
+        ),
+        day(
+            3,
+            "Crossed Wires",
+            &["grid"],
+            &[&d03::part_a, &d03::part_b],
+        ),
+        day(
+            4,
+            "Secure Container",
+            &["brute-force"],
+            &[&d04::part_a, &d04::part_b],
+        ),
+        day(
+            5,
+            "Sunny with a Chance of Asteroids",
+            &["intcode"],
+            &[&d05::part_a, &d05::part_b],
+        ),
+        day(
+            6,
+            "Universal Orbit Map",
+            &["graph"],
+            &[&d06::part_a, &d06::part_b],
+        ),
+        day(
+            7,
+            "Amplification Circuit",
+            &["intcode", "permutations"],
+            &[&d07::part_a, &d07::part_b],
+        ),
+        day(
+            8,
+            "Space Image Format",
+            &["image"],
+            &[&d08::part_a, &d08::part_b],
+        ),
+        day(
+            9,
+            "Sensor Boost",
+            &["intcode"],
+            &[&d09::part_a, &d09::part_b],
+        ),
+        day(
+            10,
+            "Monitoring Station",
+            &["grid", "geometry"],
+            &[&d10::part_a, &d10::part_b],
+        ),
+        day(
+            11,
+            "Space Police",
+            &["intcode", "grid"],
+            &[&d11::part_a, &d11::part_b],
+        ),
+        day(
+            12,
+            "The N-Body Problem",
+            &["simulation", "number-theory"],
+            &[&d12::part_a, &d12::part_b],
+        ),
+        day(
+            13,
+            "Care Package",
+            &["intcode", "game"],
+            &[&d13::part_a, &d13::part_b],
+        ),
+        day(
+            14,
+            "Space Stoichiometry",
+            &["graph", "search"],
+            &[&d14::part_a, &d14::part_b],
+        ),
+        day(
+            15,
+            "Oxygen System",
+            &["intcode", "pathfinding"],
+            &[&d15::part_a, &d15::part_b],
+        ),
+        day(
+            16,
+            "Flawed Frequency Transmission",
+            &["signal"],
+            &[&d16::part_a, &d16::part_b],
+        ),
+        day(
+            17,
+            "Set and Forget",
+            &["intcode", "grid", "ascii"],
+            &[&d17::part_a, &d17::part_b],
+        ),
+        day(
+            18,
+            "Many-Worlds Interpretation",
+            &["grid", "pathfinding"],
+            &[&d18::part_a, &d18::part_b],
+        ),
+        day(
+            19,
+            "Tractor Beam",
+            &["intcode", "search"],
+            &[&d19::part_a, &d19::part_b],
+        ),
+        day(
+            20,
+            "Donut Maze",
+            &["grid", "pathfinding"],
+            &[&d20::part_a, &d20::part_b],
+        ),
+        day(
+            21,
+            "Springdroid Adventure",
+            &["intcode", "ascii"],
+            &[&d21::part_a, &d21::part_b],
+        ),
+        day(
+            22,
+            "Slam Shuffle",
+            &["number-theory"],
+            &[&d22::part_a, &d22::part_b],
+        ),
+        day(
+            23,
+            "Category Six",
+            &["intcode", "network"],
+            &[&d23::part_a, &d23::part_b],
+        ),
+        day(
+            24,
+            "Planet of Discord",
+            &["grid", "simulation"],
+            &[&d24::part_a, &d24::part_b],
+        ),
+        day(
+            25,
+            "Cryostasis",
+            &["intcode", "ascii", "game", "search"],
+            &[&d25::part_a],
+        ),
+    ]
+    .concat();
+}
+
+/// Number of parts solved for each day, in order of day
+pub fn parts_per_day() -> Vec<usize> {
+    let num_days = SOLVERS.iter().map(|s| s.day).max().unwrap_or(0);
+    (1..=num_days)
+        .map(|d| SOLVERS.iter().filter(|s| s.day == d).count())
+        .collect()
 }
@@ -0,0 +1,227 @@
+/// Wrapper for Intcode programs that talk in lines of ASCII text
+///
+/// Tabs, newlines and printable characters output by the program are collected into lines. Any
+/// value beyond ASCII, such as the answer a program prints after its text, is kept separately as a
+/// result. Other control characters can't be either, so they are reported as errors.
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::time::Duration;
+
+use super::{Intcode, IntcodeError, IntcodeIo, RunState};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AsciiError {
+    /// Text to be sent included a character that isn't ASCII
+    NotAscii(char),
+    /// The program output a control character, which is neither text nor a result
+    InvalidOutput(i64),
+    Fault(IntcodeError),
+}
+
+impl Display for AsciiError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            AsciiError::NotAscii(c) => write!(f, "cannot send non-ASCII character {:?}", c),
+            AsciiError::InvalidOutput(v) => write!(f, "output {} is not a text character", v),
+            AsciiError::Fault(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for AsciiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AsciiError::Fault(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Text waiting to be read by the program and what it has output so far
+#[derive(Debug, Clone, Default)]
+struct Terminal {
+    input: VecDeque<i64>,
+    /// Text output since the last newline
+    line: String,
+    lines: VecDeque<String>,
+    results: VecDeque<i64>,
+    error: Option<AsciiError>,
+}
+
+impl IntcodeIo for Terminal {
+    fn input(&mut self) -> Option<i64> {
+        // Stop the program at its next input once its output has gone wrong
+        match self.error {
+            Some(_) => None,
+            None => self.input.pop_front(),
+        }
+    }
+
+    fn output(&mut self, value: i64) {
+        match value {
+            10 => self.lines.push_back(self.line.split_off(0)),
+            9 | 32..=126 => self.line.push(value as u8 as char),
+            0..=127 => {
+                self.error.get_or_insert(AsciiError::InvalidOutput(value));
+            }
+            _ => self.results.push_back(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AsciiIntcode {
+    program: Intcode,
+    terminal: Terminal,
+}
+
+impl AsciiIntcode {
+    pub fn new(program: Intcode) -> Self {
+        Self {
+            program,
+            terminal: Terminal::default(),
+        }
+    }
+
+    pub fn program(&self) -> &Intcode {
+        &self.program
+    }
+
+    pub fn program_mut(&mut self) -> &mut Intcode {
+        &mut self.program
+    }
+
+    /// Queues text to be read by the program. Nothing is sent if any of it isn't ASCII.
+    pub fn send(&mut self, text: &str) -> Result<(), AsciiError> {
+        if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+            return Err(AsciiError::NotAscii(c));
+        }
+        self.terminal.input.extend(text.bytes().map(i64::from));
+        Ok(())
+    }
+
+    /// Queues a line of text to be read by the program, adding the newline
+    pub fn send_line(&mut self, line: &str) -> Result<(), AsciiError> {
+        self.send(line)?;
+        self.terminal.input.push_back(10);
+        Ok(())
+    }
+
+    /// Runs until the program halts or has read all the text sent. Stops at the next input
+    /// after an invalid character is output, with the error returned.
+    pub fn run(&mut self) -> Result<RunState, AsciiError> {
+        let state = self.program.run_with(&mut self.terminal);
+        self.finish(state)
+    }
+
+    /// Runs like `run`, but stops once the given number of instructions have been executed
+    pub fn run_with_budget(&mut self, instructions: usize) -> Result<RunState, AsciiError> {
+        let state = self
+            .program
+            .run_io_with_budget(&mut self.terminal, instructions);
+        self.finish(state)
+    }
+
+    /// Runs like `run`, but stops once the given time has passed
+    pub fn run_for(&mut self, duration: Duration) -> Result<RunState, AsciiError> {
+        let state = self.program.run_io_for(&mut self.terminal, duration);
+        self.finish(state)
+    }
+
+    fn finish(&mut self, state: Result<RunState, IntcodeError>) -> Result<RunState, AsciiError> {
+        let state = state.map_err(AsciiError::Fault)?;
+        match self.terminal.error.take() {
+            Some(err) => Err(err),
+            None => Ok(state),
+        }
+    }
+
+    /// Takes the next complete line output, without its newline
+    pub fn read_line(&mut self) -> Option<String> {
+        self.terminal.lines.pop_front()
+    }
+
+    /// Takes every complete line output so far
+    pub fn lines(&mut self) -> impl Iterator<Item = String> + '_ {
+        self.terminal.lines.drain(..)
+    }
+
+    /// Takes all text output so far, including any line not yet finished
+    pub fn take_text(&mut self) -> String {
+        let mut text = String::new();
+        for line in self.terminal.lines.drain(..) {
+            text += &line;
+            text.push('\n');
+        }
+        text + &self.terminal.line.split_off(0)
+    }
+
+    /// Takes the values output beyond ASCII so far
+    pub fn results(&mut self) -> impl Iterator<Item = i64> + '_ {
+        self.terminal.results.drain(..)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Program that outputs every value it reads
+    fn echo() -> AsciiIntcode {
+        AsciiIntcode::new("3,100,4,100,1105,1,0".parse().unwrap())
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut echo = echo();
+        echo.send_line("Hello").unwrap();
+        echo.send("world").unwrap();
+        assert_eq!(echo.run(), Ok(RunState::AwaitingInput));
+        assert_eq!(echo.read_line(), Some("Hello".to_string()));
+        assert_eq!(echo.read_line(), None);
+        echo.send_line("!\tok").unwrap();
+        echo.run().unwrap();
+        assert_eq!(echo.take_text(), "world!\tok\n");
+        assert_eq!(echo.take_text(), "");
+    }
+
+    #[test]
+    fn test_results() {
+        let program = "104,97,104,1000000,104,10,104,-1,99".parse().unwrap();
+        let mut ascii = AsciiIntcode::new(program);
+        assert_eq!(ascii.run(), Ok(RunState::Halted));
+        assert_eq!(ascii.results().collect::<Vec<i64>>(), vec![1_000_000, -1]);
+        assert_eq!(ascii.lines().collect::<Vec<String>>(), vec!["a"]);
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut echo = echo();
+        echo.send("hi").unwrap();
+        assert_eq!(echo.run_with_budget(4), Ok(RunState::OutOfBudget));
+        assert_eq!(echo.take_text(), "h");
+        assert_eq!(echo.run_with_budget(100), Ok(RunState::AwaitingInput));
+        assert_eq!(echo.take_text(), "i");
+
+        let mut spin = AsciiIntcode::new("104,33,1105,1,2".parse().unwrap());
+        assert_eq!(
+            spin.run_for(Duration::from_millis(10)),
+            Ok(RunState::OutOfBudget)
+        );
+        assert_eq!(spin.take_text(), "!");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut echo = echo();
+        assert_eq!(echo.send_line("café"), Err(AsciiError::NotAscii('é')));
+        echo.send("ok\x07").unwrap();
+        assert_eq!(echo.run(), Err(AsciiError::InvalidOutput(7)));
+        assert_eq!(echo.take_text(), "ok");
+        assert_eq!(
+            AsciiError::InvalidOutput(7).to_string(),
+            "output 7 is not a text character"
+        );
+    }
+}
@@ -0,0 +1,532 @@
+/// Assembler for Intcode programs
+///
+/// Each line holds an optional address check such as `12:`, any number of labels such as
+/// `loop:`, then an instruction or directive, with `;` starting a comment. Operands are written
+/// as `[n]` for position mode, `#n` for immediate mode and `[rel+n]` or `[rel-n]` for relative
+/// mode, where `n` may be a number, a label or a label with an offset. `DATA` lists values and `ASCII` a quoted
+/// string with `\n`, `\"` and `\\` escapes. Listings from the disassembler assemble back to the
+/// same memory.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::disasm::{Instruction, Op, Operand};
+use super::Intcode;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for AsmError {}
+
+/// Value that may refer to a label, to be resolved once every label is known
+#[derive(Debug, Clone)]
+struct Expr {
+    label: Option<(String, usize, usize)>,
+    /// Whether the label's address is subtracted rather than added
+    negated: bool,
+    offset: i64,
+}
+
+impl Expr {
+    fn number(value: i64) -> Self {
+        Self {
+            label: None,
+            negated: false,
+            offset: value,
+        }
+    }
+
+    fn negate(self) -> Option<Self> {
+        Some(Self {
+            label: self.label,
+            negated: !self.negated,
+            offset: self.offset.checked_neg()?,
+        })
+    }
+
+    fn resolve(&self, labels: &HashMap<String, usize>) -> Result<i64, AsmError> {
+        let (name, line, column) = match &self.label {
+            None => return Ok(self.offset),
+            Some(label) => label,
+        };
+        let error = |message| AsmError {
+            line: *line,
+            column: *column,
+            message,
+        };
+        let address = match labels.get(name) {
+            Some(&address) => address as i64,
+            None => return Err(error(format!("unknown label {:?}", name))),
+        };
+        let value = if self.negated {
+            self.offset.checked_sub(address)
+        } else {
+            self.offset.checked_add(address)
+        };
+        value.ok_or_else(|| error(format!("value of {:?} is out of range", name)))
+    }
+}
+
+/// Builds an operand in one parameter mode from its resolved value
+type Mode = fn(i64) -> Operand;
+
+#[derive(Debug)]
+enum Statement {
+    Instruction(Op, Vec<(Mode, Expr)>),
+    Data(Vec<Expr>),
+}
+
+impl Statement {
+    fn size(&self) -> usize {
+        match self {
+            Statement::Instruction(_, operands) => 1 + operands.len(),
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+/// Position within a line of source being parsed
+struct Cursor<'a> {
+    line: usize,
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(line: usize, text: &'a str) -> Self {
+        // Comments run to the end of the line, except within a string
+        let mut quoted = false;
+        let mut escaped = false;
+        let end = text
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '"' if !escaped => quoted = !quoted,
+                    ';' if !quoted => return true,
+                    _ => (),
+                }
+                escaped = c == '\\' && !escaped;
+                false
+            })
+            .map_or(text.len(), |(i, _)| i);
+        Self {
+            line,
+            text: &text[..end],
+            position: 0,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn column(&self) -> usize {
+        self.text[..self.position].chars().count() + 1
+    }
+
+    fn error(&self, message: impl Into<String>) -> AsmError {
+        AsmError {
+            line: self.line,
+            column: self.column(),
+            message: message.into(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.rest().is_empty()
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), AsmError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {:?}", c)))
+        }
+    }
+
+    /// Takes the longest run of characters matching a predicate
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.position += len;
+        &rest[..len]
+    }
+
+    fn word(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        if self
+            .rest()
+            .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        {
+            Some(self.take_while(|c| c.is_ascii_alphanumeric() || c == '_'))
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Result<i64, AsmError> {
+        self.skip_whitespace();
+        let column = self.column();
+        let sign = if self.eat('-') { "-" } else { "" };
+        self.skip_whitespace();
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        // Parsed with the sign so that the most negative value fits
+        let message = match (sign.to_string() + digits).parse::<i64>() {
+            Ok(value) => return Ok(value),
+            Err(_) if digits.is_empty() => "expected a number",
+            Err(_) => "number is out of range",
+        };
+        Err(AsmError {
+            line: self.line,
+            column,
+            message: message.to_string(),
+        })
+    }
+
+    /// Whether the next character is a minus sign before a number rather than a label
+    fn at_negative_number(&mut self) -> bool {
+        self.peek() == Some('-')
+            && self.rest()[1..]
+                .trim_start()
+                .starts_with(|c: char| c.is_ascii_digit())
+    }
+
+    /// Parses a number, a label, or a label followed by `+n` or `-n`
+    fn expr(&mut self) -> Result<Expr, AsmError> {
+        self.skip_whitespace();
+        let column = self.column();
+        match self.word() {
+            Some(label) => {
+                let offset = match self.peek() {
+                    Some('+') => {
+                        self.eat('+');
+                        self.number()?
+                    }
+                    Some('-') => self.number()?,
+                    _ => 0,
+                };
+                Ok(Expr {
+                    label: Some((label.to_string(), self.line, column)),
+                    negated: false,
+                    offset,
+                })
+            }
+            None => Ok(Expr::number(self.number()?)),
+        }
+    }
+
+    fn operand(&mut self) -> Result<(Mode, Expr), AsmError> {
+        if self.eat('#') {
+            return Ok((Operand::Immediate, self.expr()?));
+        }
+        if !self.eat('[') {
+            return Err(self.error("expected an operand such as [n], #n or [rel+n]"));
+        }
+        let start = self.position;
+        let relative = match self.word() {
+            Some(w) if w.eq_ignore_ascii_case("rel") => {
+                matches!(self.peek(), Some('+' | '-' | ']'))
+            }
+            _ => false,
+        };
+        let operand = if relative {
+            let expr = match self.peek() {
+                Some('+') => {
+                    self.eat('+');
+                    self.expr()?
+                }
+                Some('-') if self.at_negative_number() => Expr::number(self.number()?),
+                Some('-') => {
+                    self.eat('-');
+                    let column = self.column();
+                    self.expr()?.negate().ok_or(AsmError {
+                        line: self.line,
+                        column,
+                        message: "number is out of range".to_string(),
+                    })?
+                }
+                _ => Expr::number(0),
+            };
+            (Operand::Relative as Mode, expr)
+        } else {
+            self.position = start;
+            (Operand::Position as Mode, self.expr()?)
+        };
+        self.expect(']')?;
+        Ok(operand)
+    }
+
+    fn string(&mut self) -> Result<Vec<Expr>, AsmError> {
+        self.expect('"')?;
+        let mut values = Vec::new();
+        let mut chars = self.rest().char_indices();
+        loop {
+            let c = match chars.next() {
+                Some((i, '"')) => {
+                    self.position += i + 1;
+                    return Ok(values);
+                }
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, 'n')) => '\n',
+                    Some((_, c @ ('"' | '\\'))) => c,
+                    Some((i, c)) => {
+                        self.position += i;
+                        return Err(self.error(format!("unknown escape \\{}", c)));
+                    }
+                    None => break,
+                },
+                Some((i, c)) if !c.is_ascii() => {
+                    self.position += i;
+                    return Err(self.error(format!("non-ASCII character {:?}", c)));
+                }
+                Some((_, c)) => c,
+                None => break,
+            };
+            values.push(Expr::number(c as i64));
+        }
+        self.position = self.text.len();
+        Err(self.error("unterminated string"))
+    }
+
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, AsmError>,
+    ) -> Result<Vec<T>, AsmError> {
+        let mut items = vec![item(self)?];
+        while self.eat(',') {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    fn statement(&mut self, name: &str, column: usize) -> Result<Statement, AsmError> {
+        let statement = if name.eq_ignore_ascii_case("data") {
+            Statement::Data(self.list(Self::expr)?)
+        } else if name.eq_ignore_ascii_case("ascii") {
+            Statement::Data(self.string()?)
+        } else {
+            let op = Op::from_mnemonic(name).ok_or_else(|| AsmError {
+                line: self.line,
+                column,
+                message: format!("unknown instruction {:?}", name),
+            })?;
+            let operands = match op.arity() {
+                0 => vec![],
+                _ => self.list(Self::operand)?,
+            };
+            if operands.len() != op.arity() {
+                return Err(AsmError {
+                    line: self.line,
+                    column,
+                    message: format!(
+                        "{} takes {} operands, not {}",
+                        op.mnemonic(),
+                        op.arity(),
+                        operands.len()
+                    ),
+                });
+            }
+            Statement::Instruction(op, operands)
+        };
+        if !self.at_end() {
+            return Err(self.error("unexpected text after statement"));
+        }
+        Ok(statement)
+    }
+}
+
+/// Assembles source into the memory image of a program
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    let mut labels = HashMap::<String, usize>::new();
+    let mut statements = Vec::new();
+    let mut address = 0;
+
+    for (number, text) in source.lines().enumerate() {
+        let mut cursor = Cursor::new(number + 1, text);
+        if cursor.peek().is_some_and(|c| c.is_ascii_digit()) {
+            let column = cursor.column();
+            let expected = cursor.number()?;
+            cursor.expect(':')?;
+            if expected != address as i64 {
+                return Err(AsmError {
+                    line: cursor.line,
+                    column,
+                    message: format!("expected address {} but reached {}", expected, address),
+                });
+            }
+        }
+        while !cursor.at_end() {
+            let column = cursor.column();
+            let name = cursor
+                .word()
+                .ok_or_else(|| cursor.error("expected a label or instruction"))?;
+            if cursor.eat(':') {
+                if labels.insert(name.to_string(), address).is_some() {
+                    return Err(AsmError {
+                        line: cursor.line,
+                        column,
+                        message: format!("label {:?} is defined more than once", name),
+                    });
+                }
+            } else {
+                let statement = cursor.statement(name, column)?;
+                address += statement.size();
+                statements.push(statement);
+            }
+        }
+    }
+
+    let mut memory = Vec::with_capacity(address);
+    for statement in statements {
+        match statement {
+            Statement::Instruction(op, operands) => {
+                let operands = operands
+                    .iter()
+                    .map(|(mode, expr)| Ok(mode(expr.resolve(&labels)?)))
+                    .collect::<Result<Vec<Operand>, AsmError>>()?;
+                let instruction = Instruction { op, operands };
+                memory.extend(instruction.encode());
+            }
+            Statement::Data(values) => {
+                for value in values {
+                    memory.push(value.resolve(&labels)?);
+                }
+            }
+        }
+    }
+    Ok(memory)
+}
+
+/// Assembles source into a program ready to run
+pub fn program(source: &str) -> Result<Intcode, AsmError> {
+    Ok(Intcode::new(assemble(source)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::disasm::listing;
+    use crate::intcode::RunState;
+    use crate::INPUTS;
+    use std::iter::once;
+
+    #[test]
+    fn test_assemble() {
+        let source = "
+            ; Counts down from the input, outputting each number
+                    IN   [counter]
+            loop:   OUT  [counter]
+                    ADD  [counter], #-1, [counter]
+                    JT   [counter], #loop
+                    HLT
+            counter: DATA 0
+        ";
+        let mut program = program(source).unwrap();
+        assert_eq!(
+            program.to_string(),
+            "3,12,4,12,1001,12,-1,12,1005,12,2,99,0"
+        );
+        assert_eq!(program.run(once(3)), Ok(RunState::Halted));
+        assert_eq!(program.collect::<Vec<i64>>(), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_operands() {
+        let source = "RBO [rel-2]\nMUL [rel], #end+1, [rel+3]\nend: ASCII \"A;\\n\"";
+        assert_eq!(
+            assemble(source).unwrap(),
+            vec![209, -2, 21202, 0, 7, 3, 65, 59, 10]
+        );
+
+        let source = "start: RBO [rel-end]\nRBO [rel - end+1]\nOUT #-9223372036854775808\nend: HLT";
+        assert_eq!(
+            assemble(source).unwrap(),
+            vec![209, -6, 209, -7, 104, i64::MIN, 99]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |source| assemble(source).unwrap_err().to_string();
+        assert_eq!(
+            error("ADD #1, #2"),
+            "line 1, column 1: ADD takes 3 operands, not 2"
+        );
+        assert_eq!(
+            error("\n  JT #1, #nowhere"),
+            "line 2, column 11: unknown label \"nowhere\""
+        );
+        assert_eq!(
+            error("OUT 5"),
+            "line 1, column 5: expected an operand such as [n], #n or [rel+n]"
+        );
+        assert_eq!(
+            error("NOP"),
+            "line 1, column 1: unknown instruction \"NOP\""
+        );
+        assert_eq!(
+            error("a: a: HLT"),
+            "line 1, column 4: label \"a\" is defined more than once"
+        );
+        assert_eq!(
+            error("HLT\n2: HLT"),
+            "line 2, column 1: expected address 2 but reached 1"
+        );
+        assert_eq!(error("OUT [3"), "line 1, column 7: expected ']'");
+        assert_eq!(
+            error("OUT #9223372036854775808"),
+            "line 1, column 6: number is out of range"
+        );
+        assert_eq!(
+            error("HLT\na: OUT #a+9223372036854775807"),
+            "line 2, column 9: value of \"a\" is out of range"
+        );
+        assert_eq!(
+            error("ASCII \"abc"),
+            "line 1, column 11: unterminated string"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for day in [5, 7, 9, 11, 13, 15, 17, 19, 21, 23, 25] {
+            let program = INPUTS[day - 1].trim().parse::<Intcode>().unwrap();
+            let memory = program.memory();
+            assert_eq!(assemble(&listing(memory)).unwrap(), memory, "day {}", day);
+        }
+    }
+}
@@ -0,0 +1,511 @@
+/// Interactive step debugger for Intcode programs
+///
+/// Commands are read a line at a time, so the debugger can be driven from a terminal or a script.
+/// Type `help` for the list of commands.
+use std::fs::File;
+use std::io::{BufRead, BufReader, Result as IoResult, Write};
+
+use super::disasm::{Instruction, Op};
+use super::{Intcode, IntcodeError, RunState};
+
+/// Values shown on each line of a memory dump
+const DUMP_PER_LINE: usize = 8;
+
+/// Instructions `continue` executes before handing control back, so a program that never stops
+/// can't hang the debugger
+const RESUME_LIMIT: usize = 10_000_000;
+
+const HELP: &str = "\
+Commands:
+  s, step [N]          execute N instructions (default 1)
+  c, continue          run until a breakpoint, watchpoint, input request, halt or fault, or
+                       for at most 10 million instructions
+  b, break ADDR|OP     stop before the instruction at ADDR, or before any OP such as OUT
+  d, delete ADDR|OP    remove a breakpoint
+  w, watch ADDR        stop after the value at ADDR changes
+  unwatch ADDR         remove a watchpoint
+  i, info              show the pointer, relative base, queues, breakpoints and watchpoints
+  l, list [ADDR] [N]   disassemble N instructions from ADDR (default the pointer and 10)
+  x, dump ADDR [N]     show N values of memory from ADDR (default 16)
+  in VALUE...          push input values, separated by spaces or commas
+  text TEXT            push a line of ASCII text as input, ending with a newline
+  out                  take and show the values output so far
+  save FILE            write a snapshot of the program to FILE
+  load FILE            replace the program with a snapshot read from FILE
+  h, help              show this message
+  q, quit              leave the debugger";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Breakpoint {
+    Address(usize),
+    Op(Op),
+}
+
+impl Breakpoint {
+    fn parse(s: &str) -> Result<Self, String> {
+        match (s.parse::<usize>(), Op::from_mnemonic(s)) {
+            (Ok(address), _) => Ok(Breakpoint::Address(address)),
+            (_, Some(op)) => Ok(Breakpoint::Op(op)),
+            _ => Err(format!("expected an address or mnemonic, not {:?}", s)),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Breakpoint::Address(address) => address.to_string(),
+            Breakpoint::Op(op) => op.mnemonic().to_string(),
+        }
+    }
+}
+
+/// Why the program stopped running under the debugger
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Stop {
+    /// Finished the requested number of steps
+    Stepped,
+    Breakpoint(usize),
+    Watchpoint {
+        address: usize,
+        old: i64,
+        new: i64,
+    },
+    AwaitingInput,
+    Halted,
+    Fault(IntcodeError),
+    /// Ran the most instructions allowed without stopping, and can be continued
+    Limit(usize),
+}
+
+/// Intcode program with breakpoints and watchpoints
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    program: Intcode,
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<usize>,
+    limit: usize,
+}
+
+impl Debugger {
+    pub fn new(program: Intcode) -> Self {
+        Self {
+            program,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            limit: RESUME_LIMIT,
+        }
+    }
+
+    /// Sets the most instructions `resume` executes before stopping
+    pub fn set_limit(&mut self, instructions: usize) {
+        self.limit = instructions.max(1);
+    }
+
+    pub fn program(&self) -> &Intcode {
+        &self.program
+    }
+
+    fn value(&self, address: usize) -> i64 {
+        self.program.value(address)
+    }
+
+    fn at_breakpoint(&self) -> bool {
+        let pointer = self.program.pointer();
+        let op = Op::from_code(self.value(pointer) % 100);
+        self.breakpoints.iter().any(|b| match *b {
+            Breakpoint::Address(address) => address == pointer,
+            Breakpoint::Op(o) => Some(o) == op,
+        })
+    }
+
+    /// Executes one instruction, checking watchpoints afterwards
+    fn step_once(&mut self) -> Option<Stop> {
+        let watched = self
+            .watchpoints
+            .iter()
+            .map(|&a| (a, self.value(a)))
+            .collect::<Vec<(usize, i64)>>();
+        match self.program.step() {
+            Err(err) => return Some(Stop::Fault(err)),
+            Ok(Some(RunState::AwaitingInput)) => return Some(Stop::AwaitingInput),
+            Ok(Some(RunState::Halted)) => return Some(Stop::Halted),
+            Ok(_) => (),
+        }
+        watched
+            .into_iter()
+            .find(|&(a, old)| self.value(a) != old)
+            .map(|(address, old)| Stop::Watchpoint {
+                address,
+                old,
+                new: self.value(address),
+            })
+    }
+
+    /// Executes up to a number of instructions, stopping early at a watchpoint or if the
+    /// program can't continue. Breakpoints are ignored.
+    pub fn step(&mut self, count: usize) -> Stop {
+        (0..count)
+            .find_map(|_| self.step_once())
+            .unwrap_or(Stop::Stepped)
+    }
+
+    /// Runs until a breakpoint or watchpoint is hit, the program can't continue, or the limit on
+    /// instructions is reached. A breakpoint at the current instruction does not stop it from
+    /// running.
+    pub fn resume(&mut self) -> Stop {
+        if let Some(stop) = self.step_once() {
+            return stop;
+        }
+        for _ in 1..self.limit {
+            if self.at_breakpoint() {
+                return Stop::Breakpoint(self.program.pointer());
+            }
+            if let Some(stop) = self.step_once() {
+                return stop;
+            }
+        }
+        Stop::Limit(self.limit)
+    }
+
+    fn describe(&self, stop: &Stop) -> String {
+        let output = self.program.output().len();
+        let pending = if output > 0 {
+            format!(" ({} values output)", output)
+        } else {
+            String::new()
+        };
+        let reason = match stop {
+            Stop::Stepped => String::new(),
+            Stop::Breakpoint(address) => format!("Breakpoint at {}{}\n", address, pending),
+            Stop::Watchpoint { address, old, new } => {
+                format!("Watchpoint [{}]: {} -> {}{}\n", address, old, new, pending)
+            }
+            Stop::AwaitingInput => format!("Awaiting input{}\n", pending),
+            Stop::Halted => format!("Halted{}\n", pending),
+            Stop::Fault(err) => format!("Fault: {}\n", err),
+            Stop::Limit(count) => format!(
+                "Still running after {} instructions, continue to run further{}\n",
+                count, pending
+            ),
+        };
+        reason + &self.list(self.program.pointer(), 1)
+    }
+
+    /// Disassembles instructions from an address, marking the one at the pointer
+    fn list(&self, address: usize, count: usize) -> String {
+        let memory = self.program.memory();
+        let mut address = address;
+        let mut lines = String::new();
+        for _ in 0..count {
+            if address >= memory.len() {
+                break;
+            }
+            let marker = if address == self.program.pointer() {
+                "=>"
+            } else {
+                "  "
+            };
+            match Instruction::decode(memory, address) {
+                Some(instruction) => {
+                    lines += &format!("{} {:>6}: {}\n", marker, address, instruction);
+                    address += instruction.size();
+                }
+                None => {
+                    lines += &format!("{} {:>6}: DATA {}\n", marker, address, memory[address]);
+                    address += 1;
+                }
+            }
+        }
+        lines
+    }
+
+    fn dump(&self, address: usize, count: usize) -> String {
+        let last = address.saturating_add(count);
+        (address..last)
+            .step_by(DUMP_PER_LINE)
+            .map(|start| {
+                let end = start.saturating_add(DUMP_PER_LINE).min(last);
+                let values = (start..end)
+                    .map(|a| format!("{:>8}", self.value(a)))
+                    .collect::<String>();
+                format!("{:>6}:{}\n", start, values)
+            })
+            .collect()
+    }
+
+    fn info(&self) -> String {
+        let list = |values: &mut dyn Iterator<Item = String>| {
+            let values = values.collect::<Vec<String>>();
+            if values.is_empty() {
+                "none".to_string()
+            } else {
+                values.join(", ")
+            }
+        };
+        format!(
+            "pointer: {}\nbase: {}\ninput: {}\noutput: {}\nbreakpoints: {}\nwatchpoints: {}\n{}",
+            self.program.pointer(),
+            self.program.base(),
+            list(&mut self.program.input().iter().map(|v| v.to_string())),
+            list(&mut self.program.output().iter().map(|v| v.to_string())),
+            list(&mut self.breakpoints.iter().map(|b| b.describe())),
+            list(&mut self.watchpoints.iter().map(|w| w.to_string())),
+            self.list(self.program.pointer(), 1)
+        )
+    }
+
+    fn take_output(&mut self) -> String {
+        let values = self.program.drain().collect::<Vec<i64>>();
+        if values.is_empty() {
+            return "No output\n".to_string();
+        }
+        let text = values
+            .iter()
+            .map(|&v| match v {
+                9 | 10 | 32..=126 => Some(v as u8 as char),
+                _ => None,
+            })
+            .collect::<Option<String>>();
+        match text {
+            Some(text) if text.ends_with('\n') => text,
+            _ => {
+                let values = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                values.join(",") + "\n"
+            }
+        }
+    }
+
+    /// Runs one command, returning the text to show or `None` to quit
+    pub fn command(&mut self, line: &str) -> Result<Option<String>, String> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let args = rest.split_whitespace().collect::<Vec<&str>>();
+        let number = |i: usize, default: Option<usize>| -> Result<usize, String> {
+            match (args.get(i), default) {
+                (Some(a), _) => a
+                    .parse()
+                    .map_err(|_| format!("expected a number, not {:?}", a)),
+                (None, Some(d)) => Ok(d),
+                (None, None) => Err(format!("{} needs an argument", name)),
+            }
+        };
+        let output = match name {
+            "" => String::new(),
+            "s" | "step" => {
+                let stop = self.step(number(0, Some(1))?);
+                self.describe(&stop)
+            }
+            "c" | "continue" => {
+                let stop = self.resume();
+                self.describe(&stop)
+            }
+            "b" | "break" => {
+                let breakpoint = Breakpoint::parse(args.first().ok_or("break needs an argument")?)?;
+                if !self.breakpoints.contains(&breakpoint) {
+                    self.breakpoints.push(breakpoint);
+                }
+                format!("Breakpoint set at {}\n", breakpoint.describe())
+            }
+            "d" | "delete" => {
+                let breakpoint =
+                    Breakpoint::parse(args.first().ok_or("delete needs an argument")?)?;
+                self.breakpoints.retain(|&b| b != breakpoint);
+                format!("Breakpoint removed at {}\n", breakpoint.describe())
+            }
+            "w" | "watch" => {
+                let address = number(0, None)?;
+                if !self.watchpoints.contains(&address) {
+                    self.watchpoints.push(address);
+                }
+                format!("Watching [{}] = {}\n", address, self.value(address))
+            }
+            "unwatch" => {
+                let address = number(0, None)?;
+                self.watchpoints.retain(|&w| w != address);
+                format!("No longer watching [{}]\n", address)
+            }
+            "i" | "info" => self.info(),
+            "l" | "list" => self.list(
+                number(0, Some(self.program.pointer()))?,
+                number(1, Some(10))?,
+            ),
+            "x" | "dump" => self.dump(number(0, None)?, number(1, Some(16))?),
+            "in" => {
+                let values = rest
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|v| !v.is_empty())
+                    .map(|v| {
+                        v.parse::<i64>()
+                            .map_err(|_| format!("expected a number, not {:?}", v))
+                    })
+                    .collect::<Result<Vec<i64>, String>>()?;
+                values.iter().for_each(|&v| self.program.push_input(v));
+                format!("Pushed {} values\n", values.len())
+            }
+            "text" => {
+                let text = format!("{}\n", rest);
+                text.bytes().for_each(|b| self.program.push_input(b as i64));
+                format!("Pushed {} characters\n", text.len())
+            }
+            "out" => self.take_output(),
+            "save" => {
+                let path = args.first().ok_or("save needs a file name")?;
+                File::create(path)
+                    .and_then(|file| self.program.save(file))
+                    .map_err(|err| format!("could not save {}: {}", path, err))?;
+                format!("Saved snapshot to {}\n", path)
+            }
+            "load" => {
+                let path = args.first().ok_or("load needs a file name")?;
+                self.program = File::open(path)
+                    .and_then(|file| Intcode::load(BufReader::new(file)))
+                    .map_err(|err| format!("could not load {}: {}", path, err))?;
+                format!(
+                    "Loaded snapshot from {}\n{}",
+                    path,
+                    self.list(self.program.pointer(), 1)
+                )
+            }
+            "h" | "help" => format!("{}\n", HELP),
+            "q" | "quit" => return Ok(None),
+            _ => return Err(format!("unknown command {:?}, try help", name)),
+        };
+        Ok(Some(output))
+    }
+}
+
+/// Reads and runs commands until the input ends or the user quits
+pub fn repl(debugger: &mut Debugger, input: impl BufRead, mut output: impl Write) -> IoResult<()> {
+    write!(
+        output,
+        "{}(intcode) ",
+        debugger.list(debugger.program.pointer(), 1)
+    )?;
+    output.flush()?;
+    for line in input.lines() {
+        match debugger.command(&line?) {
+            Ok(Some(text)) => write!(output, "{}", text)?,
+            Ok(None) => return Ok(()),
+            Err(message) => writeln!(output, "error: {}", message)?,
+        }
+        write!(output, "(intcode) ")?;
+        output.flush()?;
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::asm::program;
+
+    fn debugger() -> Debugger {
+        let source = "
+                    IN   [counter]
+            loop:   OUT  [counter]
+                    ADD  [counter], #-1, [counter]
+                    JT   [counter], #loop
+                    HLT
+            counter: DATA 0
+        ";
+        Debugger::new(program(source).unwrap())
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut debugger = debugger();
+        assert_eq!(debugger.resume(), Stop::AwaitingInput);
+        debugger.program.push_input(3);
+        debugger.breakpoints.push(Breakpoint::Op(Op::Jt));
+        assert_eq!(debugger.resume(), Stop::Breakpoint(8));
+        assert_eq!(debugger.program.output().len(), 1);
+        debugger.breakpoints.clear();
+        debugger.watchpoints.push(12);
+        assert_eq!(
+            debugger.resume(),
+            Stop::Watchpoint {
+                address: 12,
+                old: 2,
+                new: 1
+            }
+        );
+        debugger.watchpoints.clear();
+        assert_eq!(debugger.step(10), Stop::Halted);
+        assert_eq!(
+            debugger.program.drain().collect::<Vec<i64>>(),
+            vec![3, 2, 1]
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let program = "109,9223372036854775807,109,1,99".parse().unwrap();
+        let mut debugger = Debugger::new(program);
+        let fault = Stop::Fault(IntcodeError::Overflow {
+            pointer: 2,
+            instruction: 109,
+        });
+        assert_eq!(debugger.resume(), fault);
+        assert_eq!(debugger.step(1), fault);
+        assert_eq!(debugger.program.pointer(), 2);
+    }
+
+    #[test]
+    fn test_limit() {
+        // Counts up at address 7 forever
+        let program = "1001,7,1,7,1105,1,0,0".parse().unwrap();
+        let mut debugger = Debugger::new(program);
+        debugger.set_limit(30);
+        assert_eq!(debugger.resume(), Stop::Limit(30));
+        assert_eq!(debugger.value(7), 15);
+        assert_eq!(debugger.resume(), Stop::Limit(30));
+        assert_eq!(debugger.value(7), 30);
+        debugger.breakpoints.push(Breakpoint::Address(4));
+        assert_eq!(debugger.resume(), Stop::Breakpoint(4));
+        assert_eq!(
+            debugger.describe(&Stop::Limit(30)),
+            "Still running after 30 instructions, continue to run further\n\
+             =>      4: JT   #1, #0\n"
+        );
+    }
+
+    #[test]
+    fn test_dump() {
+        let debugger = debugger();
+        assert_eq!(debugger.dump(10, 2), "    10:       2      99\n");
+        assert_eq!(
+            debugger.dump(usize::MAX - 1, usize::MAX),
+            format!("{}:       0\n", usize::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut debugger = debugger();
+        let script = "in 2\nbreak 4\nc\ninfo\nx 10 4\nnope\nc\nc\nout\nq\n";
+        let mut output = Vec::new();
+        repl(&mut debugger, script.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let expected = "\
+=>      0: IN   [12]
+(intcode) Pushed 1 values
+(intcode) Breakpoint set at 4
+(intcode) Breakpoint at 4 (1 values output)
+=>      4: ADD  [12], #-1, [12]
+(intcode) pointer: 4
+base: 0
+input: none
+output: 2
+breakpoints: 4
+watchpoints: none
+=>      4: ADD  [12], #-1, [12]
+(intcode)     10:       2      99       2       0
+(intcode) error: unknown command \"nope\", try help
+(intcode) Breakpoint at 4 (2 values output)
+=>      4: ADD  [12], #-1, [12]
+(intcode) Halted (2 values output)
+=>     11: HLT
+(intcode) 2,1
+(intcode) ";
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,368 @@
+/// Disassembler for Intcode memory images
+///
+/// Code is found by following the program from address 0, including every jump with an immediate
+/// target and the return address of every call. Anything never reached is listed as data.
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Most data values shown on a single line
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Op {
+    Add,
+    Mul,
+    In,
+    Out,
+    Jt,
+    Jf,
+    Lt,
+    Eq,
+    Rbo,
+    Hlt,
+}
+
+impl Op {
+    pub const ALL: [Op; 10] = [
+        Op::Add,
+        Op::Mul,
+        Op::In,
+        Op::Out,
+        Op::Jt,
+        Op::Jf,
+        Op::Lt,
+        Op::Eq,
+        Op::Rbo,
+        Op::Hlt,
+    ];
+
+    pub fn from_code(code: i64) -> Option<Self> {
+        match code {
+            1 => Some(Op::Add),
+            2 => Some(Op::Mul),
+            3 => Some(Op::In),
+            4 => Some(Op::Out),
+            5 => Some(Op::Jt),
+            6 => Some(Op::Jf),
+            7 => Some(Op::Lt),
+            8 => Some(Op::Eq),
+            9 => Some(Op::Rbo),
+            99 => Some(Op::Hlt),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> i64 {
+        match self {
+            Op::Add => 1,
+            Op::Mul => 2,
+            Op::In => 3,
+            Op::Out => 4,
+            Op::Jt => 5,
+            Op::Jf => 6,
+            Op::Lt => 7,
+            Op::Eq => 8,
+            Op::Rbo => 9,
+            Op::Hlt => 99,
+        }
+    }
+
+    /// Number of operands taken by the instruction
+    pub fn arity(self) -> usize {
+        match self {
+            Op::Add | Op::Mul | Op::Lt | Op::Eq => 3,
+            Op::Jt | Op::Jf => 2,
+            Op::In | Op::Out | Op::Rbo => 1,
+            Op::Hlt => 0,
+        }
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Op::Add => "ADD",
+            Op::Mul => "MUL",
+            Op::In => "IN",
+            Op::Out => "OUT",
+            Op::Jt => "JT",
+            Op::Jf => "JF",
+            Op::Lt => "LT",
+            Op::Eq => "EQ",
+            Op::Rbo => "RBO",
+            Op::Hlt => "HLT",
+        }
+    }
+
+    pub fn from_mnemonic(s: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|op| op.mnemonic().eq_ignore_ascii_case(s))
+    }
+}
+
+/// Operand of an instruction, shown as `[n]`, `#n` or `[rel+n]` for each mode
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Operand {
+    Position(i64),
+    Immediate(i64),
+    Relative(i64),
+}
+
+impl Operand {
+    fn decode(mode: i64, value: i64) -> Option<Self> {
+        match mode {
+            0 => Some(Operand::Position(value)),
+            1 => Some(Operand::Immediate(value)),
+            2 => Some(Operand::Relative(value)),
+            _ => None,
+        }
+    }
+
+    pub fn mode(self) -> i64 {
+        match self {
+            Operand::Position(_) => 0,
+            Operand::Immediate(_) => 1,
+            Operand::Relative(_) => 2,
+        }
+    }
+
+    pub fn value(self) -> i64 {
+        match self {
+            Operand::Position(v) | Operand::Immediate(v) | Operand::Relative(v) => v,
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            Operand::Position(v) => write!(f, "[{}]", v),
+            Operand::Immediate(v) => write!(f, "#{}", v),
+            Operand::Relative(v) if v < 0 => write!(f, "[rel-{}]", -v),
+            Operand::Relative(v) => write!(f, "[rel+{}]", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Instruction {
+    pub op: Op,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    /// Decodes the instruction at an address, if the memory there holds a valid one
+    pub fn decode(memory: &[i64], address: usize) -> Option<Self> {
+        let raw = *memory.get(address)?;
+        let op = Op::from_code(raw % 100)?;
+        let operands = (0..op.arity())
+            .map(|i| {
+                let mode = raw / 100 / 10_i64.pow(i as u32) % 10;
+                Operand::decode(mode, *memory.get(address + 1 + i)?)
+            })
+            .collect::<Option<Vec<Operand>>>()?;
+        let instruction = Self { op, operands };
+        // Only accept the canonical encoding, so the listing assembles back to the same memory
+        if instruction.encode()[0] == raw {
+            Some(instruction)
+        } else {
+            None
+        }
+    }
+
+    pub fn encode(&self) -> Vec<i64> {
+        let modes = self
+            .operands
+            .iter()
+            .rev()
+            .fold(0, |modes, operand| modes * 10 + operand.mode());
+        let mut values = vec![modes * 100 + self.op.code()];
+        values.extend(self.operands.iter().map(|o| o.value()));
+        values
+    }
+
+    /// Number of memory values taken up by the instruction
+    pub fn size(&self) -> usize {
+        1 + self.operands.len()
+    }
+
+    /// Address jumped to, if it is known without running the program
+    fn jump_target(&self) -> Option<usize> {
+        match (self.op, self.operands.get(1)) {
+            (Op::Jt, Some(Operand::Immediate(t))) | (Op::Jf, Some(Operand::Immediate(t)))
+                if *t >= 0 =>
+            {
+                Some(*t as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether execution may carry on to the next instruction
+    fn falls_through(&self) -> bool {
+        match (self.op, self.operands.first()) {
+            (Op::Hlt, _) => false,
+            (Op::Jt, Some(Operand::Immediate(c))) => *c == 0,
+            (Op::Jf, Some(Operand::Immediate(c))) => *c != 0,
+            _ => true,
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if self.operands.is_empty() {
+            return write!(f, "{}", self.op.mnemonic());
+        }
+        write!(f, "{:<4} ", self.op.mnemonic())?;
+        for (i, operand) in self.operands.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            write!(f, "{}{}", separator, operand)?;
+        }
+        Ok(())
+    }
+}
+
+/// Line of a listing, either an instruction or a run of data
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Line {
+    Instruction(usize, Instruction),
+    Data(usize, Vec<i64>),
+}
+
+impl Line {
+    pub fn address(&self) -> usize {
+        match *self {
+            Line::Instruction(address, _) | Line::Data(address, _) => address,
+        }
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Line::Instruction(address, instruction) => write!(f, "{:>6}: {}", address, instruction),
+            Line::Data(address, values) => {
+                let values = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                write!(f, "{:>6}: DATA {}", address, values.join(", "))
+            }
+        }
+    }
+}
+
+/// Addresses of every instruction reachable from the start of the program. A jump that follows
+/// storing its own return address as an immediate value is taken to be a call, so the code after
+/// it is followed too.
+fn find_code(memory: &[i64]) -> BTreeSet<usize> {
+    let mut code = BTreeSet::new();
+    let mut pending = vec![0];
+    while let Some(mut address) = pending.pop() {
+        let mut stored = Vec::new();
+        while !code.contains(&address) {
+            let instruction = match Instruction::decode(memory, address) {
+                Some(i) => i,
+                None => break,
+            };
+            code.insert(address);
+            pending.extend(instruction.jump_target());
+            address += instruction.size();
+            if !instruction.falls_through() {
+                if stored.contains(&(address as i64)) {
+                    pending.push(address);
+                }
+                break;
+            }
+            if let Op::Add | Op::Mul = instruction.op {
+                stored.extend(instruction.operands.iter().filter_map(|o| match o {
+                    Operand::Immediate(v) => Some(*v),
+                    _ => None,
+                }));
+            }
+        }
+    }
+    code
+}
+
+/// Disassembles a memory image into instructions and data
+pub fn disassemble(memory: &[i64]) -> Vec<Line> {
+    let code = find_code(memory);
+    let mut lines = Vec::new();
+    let mut address = 0;
+    while address < memory.len() {
+        match Instruction::decode(memory, address) {
+            Some(instruction) if code.contains(&address) => {
+                let len = instruction.size();
+                lines.push(Line::Instruction(address, instruction));
+                address += len;
+            }
+            _ => {
+                let end = (address + 1..memory.len())
+                    .find(|a| code.contains(a))
+                    .unwrap_or(memory.len())
+                    .min(address + DATA_PER_LINE);
+                lines.push(Line::Data(address, memory[address..end].to_vec()));
+                address = end;
+            }
+        }
+    }
+    lines
+}
+
+/// Disassembles a memory image into a listing with one line per instruction or run of data
+pub fn listing(memory: &[i64]) -> String {
+    disassemble(memory)
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let memory = [1002, 4, -3, 4, 33];
+        let instruction = Instruction::decode(&memory, 0).unwrap();
+        assert_eq!(instruction.op, Op::Mul);
+        assert_eq!(instruction.to_string(), "MUL  [4], #-3, [4]");
+        assert_eq!(instruction.encode(), vec![1002, 4, -3, 4]);
+        assert_eq!(
+            Instruction::decode(&[21101, 1, -2, 7], 0)
+                .unwrap()
+                .to_string(),
+            "ADD  #1, #-2, [rel+7]"
+        );
+        assert_eq!(
+            Instruction::decode(&[209, -1], 0).unwrap().to_string(),
+            "RBO  [rel-1]"
+        );
+        assert_eq!(Instruction::decode(&[1199], 0), None);
+        assert_eq!(Instruction::decode(&[301, 0, 0, 0], 0), None);
+        assert_eq!(Instruction::decode(&[1, 0, 0], 0), None);
+    }
+
+    #[test]
+    fn test_listing() {
+        let memory = [1105, 1, 7, 99, 72, 105, 0, 4, 4, 1105, 1, 3];
+        assert_eq!(
+            listing(&memory),
+            "     0: JT   #1, #7\n     \
+                  3: HLT\n     \
+                  4: DATA 72, 105, 0\n     \
+                  7: OUT  [4]\n     \
+                  9: JT   #1, #3\n"
+        );
+    }
+
+    #[test]
+    fn test_call() {
+        // Stores the return address 7 before jumping to 8, which jumps back
+        let memory = [1101, 0, 7, 20, 1105, 1, 8, 99, 1105, 1, 7];
+        let lines = disassemble(&memory);
+        assert!(lines
+            .iter()
+            .all(|line| matches!(line, Line::Instruction(_, _))));
+        assert_eq!(lines.len(), 4);
+    }
+}
@@ -0,0 +1,51 @@
+/// Input and output of Intcode programs
+///
+/// The program calls its I/O as each IN and OUT instruction executes, so input can be worked out
+/// on demand from the output so far. Programs hold a pair of queues by default, which are used by
+/// `run` and friends; `run_with` takes any other implementation.
+use std::collections::VecDeque;
+
+pub trait IntcodeIo {
+    /// Value to be read by an IN instruction, or `None` to stop the program until there is one
+    fn input(&mut self) -> Option<i64>;
+
+    /// Takes the value written by an OUT instruction
+    fn output(&mut self, value: i64);
+}
+
+/// Queues of values waiting to be read by the program and values it has output
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Queues {
+    pub input: VecDeque<i64>,
+    pub output: VecDeque<i64>,
+}
+
+impl IntcodeIo for Queues {
+    fn input(&mut self) -> Option<i64> {
+        self.input.pop_front()
+    }
+
+    fn output(&mut self, value: i64) {
+        self.output.push_back(value);
+    }
+}
+
+/// I/O handled by a pair of closures
+pub struct Handlers<I, O> {
+    pub input: I,
+    pub output: O,
+}
+
+impl<I, O> IntcodeIo for Handlers<I, O>
+where
+    I: FnMut() -> Option<i64>,
+    O: FnMut(i64),
+{
+    fn input(&mut self) -> Option<i64> {
+        (self.input)()
+    }
+
+    fn output(&mut self, value: i64) {
+        (self.output)(value)
+    }
+}
@@ -0,0 +1,197 @@
+/// Memory for Intcode programs
+///
+/// Addresses from zero up to a threshold are stored contiguously, as nearly every program only
+/// uses a little memory past its own code. Anything higher is stored in pages allocated on first
+/// write, so a write to a huge address costs one page rather than everything below it.
+use std::collections::HashMap;
+
+/// Highest address below which memory is stored contiguously
+const DENSE_LIMIT: usize = 1 << 16;
+
+/// Number of values in each page above the contiguous region
+const PAGE_SIZE: usize = 1 << 10;
+
+/// A write needed more memory than the limit allows
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LimitExceeded {
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Memory {
+    dense: Vec<i64>,
+    pages: HashMap<usize, Box<[i64; PAGE_SIZE]>>,
+    /// One past the highest address holding a value
+    end: usize,
+    /// Most values that may be allocated, if limited
+    limit: Option<usize>,
+}
+
+impl Memory {
+    pub fn new(image: Vec<i64>) -> Self {
+        Self {
+            end: image.len(),
+            dense: image,
+            pages: HashMap::new(),
+            limit: None,
+        }
+    }
+
+    /// Caps the number of values allocated. Memory already allocated is kept even if it exceeds
+    /// the new limit.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Number of values allocated, whether written to or not
+    pub fn allocated(&self) -> usize {
+        self.dense.len() + self.pages.len() * PAGE_SIZE
+    }
+
+    /// One past the highest address written or loaded
+    pub fn len(&self) -> usize {
+        self.end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end == 0
+    }
+
+    /// Values from address zero up to the first that isn't stored contiguously
+    pub fn image(&self) -> &[i64] {
+        &self.dense
+    }
+
+    /// Runs of values in address order with their starting addresses, covering all memory
+    /// allocated
+    pub fn chunks(&self) -> Vec<(usize, &[i64])> {
+        let mut pages = self
+            .pages
+            .iter()
+            .map(|(&page, values)| (page * PAGE_SIZE, &values[..]))
+            .collect::<Vec<(usize, &[i64])>>();
+        pages.sort_unstable_by_key(|&(address, _)| address);
+        let mut chunks = vec![(0, &self.dense[..])];
+        chunks.extend(pages);
+        chunks
+    }
+
+    /// Extends the length of memory as though zero had been written just below an address
+    pub fn extend_to(&mut self, end: usize) {
+        self.end = self.end.max(end);
+    }
+
+    /// Value at an address, where memory never written reads as zero
+    #[inline]
+    pub fn get(&self, index: usize) -> i64 {
+        match self.dense.get(index) {
+            Some(&value) => value,
+            None => self.get_paged(index),
+        }
+    }
+
+    #[cold]
+    fn get_paged(&self, index: usize) -> i64 {
+        self.pages
+            .get(&(index / PAGE_SIZE))
+            .map_or(0, |page| page[index % PAGE_SIZE])
+    }
+
+    /// Extra values that must be allocated to store a value at an address
+    fn growth(&self, index: usize) -> usize {
+        if index < self.dense.len() {
+            0
+        } else if index < DENSE_LIMIT {
+            index + 1 - self.dense.len()
+        } else if self.pages.contains_key(&(index / PAGE_SIZE)) {
+            0
+        } else {
+            PAGE_SIZE
+        }
+    }
+
+    /// Checks a value could be set at an address without exceeding the limit
+    pub fn check(&self, index: usize) -> Result<(), LimitExceeded> {
+        match self.limit {
+            Some(limit) if self.allocated() + self.growth(index) > limit => {
+                Err(LimitExceeded { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, index: usize, value: i64) -> Result<(), LimitExceeded> {
+        match self.dense.get_mut(index) {
+            Some(stored) => *stored = value,
+            None => {
+                self.check(index)?;
+                self.force(index, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets a value even if it takes memory beyond the limit
+    pub fn force(&mut self, index: usize, value: i64) {
+        if index < self.dense.len() {
+            self.dense[index] = value;
+        } else if index < DENSE_LIMIT {
+            self.dense.resize(index + 1, 0);
+            self.dense[index] = value;
+        } else {
+            let page = self
+                .pages
+                .entry(index / PAGE_SIZE)
+                .or_insert_with(|| Box::new([0; PAGE_SIZE]));
+            page[index % PAGE_SIZE] = value;
+        }
+        // The length can't go past the highest address, so a value there is counted as below it
+        self.end = self.end.max(index.saturating_add(1));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sparse() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+        memory.set(10, 4).unwrap();
+        memory.set(1_000_000_000_000, 5).unwrap();
+        assert_eq!(memory.get(1), 2);
+        assert_eq!(memory.get(10), 4);
+        assert_eq!(memory.get(1_000_000_000_000), 5);
+        assert_eq!(memory.get(1_000_000_000_001), 0);
+        assert_eq!(memory.get(usize::MAX), 0);
+        assert_eq!(memory.image().len(), 11);
+        assert_eq!(memory.len(), 1_000_000_000_001);
+        assert_eq!(memory.allocated(), 11 + PAGE_SIZE);
+
+        memory.set(usize::MAX, 6).unwrap();
+        assert_eq!(memory.get(usize::MAX), 6);
+        assert_eq!(memory.len(), usize::MAX);
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut memory = Memory::new(vec![0; 100]);
+        memory.set_limit(Some(100 + PAGE_SIZE));
+        assert_eq!(
+            memory.set(PAGE_SIZE + 100, 1),
+            Err(LimitExceeded {
+                limit: 100 + PAGE_SIZE
+            })
+        );
+        memory.set(99, 1).unwrap();
+        memory.set(1 << 40, 1).unwrap();
+        memory.set((1 << 40) + 1, 1).unwrap();
+        assert!(memory.set(1 << 41, 1).is_err());
+        assert_eq!(memory.get(1 << 41), 0);
+    }
+}
@@ -0,0 +1,923 @@
+/// The Intcode computer used by several problems
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::mem::take;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::cancel;
+use disasm::{Instruction, Op};
+use memory::{LimitExceeded, Memory};
+use profile::{SharedProfile, Trace};
+
+pub mod ascii;
+pub mod asm;
+pub mod debug;
+pub mod disasm;
+pub mod io;
+pub mod memory;
+pub mod network;
+pub mod pipeline;
+pub mod profile;
+pub mod reference;
+pub mod search;
+pub mod snapshot;
+pub mod workload;
+
+pub use self::io::{Handlers, IntcodeIo, Queues};
+
+#[derive(Debug, Copy, Clone)]
+enum Mode {
+    Immediate,
+    Position,
+    Relative,
+}
+
+/// Operation and parameter modes of an instruction
+#[derive(Debug, Copy, Clone)]
+struct Decoded {
+    op: Op,
+    /// Mode of each parameter in two bits, from the lowest for the first parameter
+    modes: u32,
+}
+
+impl Decoded {
+    fn new(raw: i64, pointer: usize) -> Result<Self, IntcodeError> {
+        let op = Op::from_code(raw % 100).ok_or(IntcodeError::InvalidOpcode {
+            pointer,
+            instruction: raw,
+        })?;
+        let mut decoded = Self { op, modes: 0 };
+        let mut modes = raw / 100;
+        for i in 0..op.arity() {
+            match modes % 10 {
+                mode @ 0..=2 => decoded.modes |= (mode as u32) << (2 * i),
+                mode => {
+                    return Err(IntcodeError::InvalidMode {
+                        pointer,
+                        instruction: raw,
+                        mode,
+                    })
+                }
+            }
+            modes /= 10;
+        }
+        Ok(decoded)
+    }
+
+    #[inline(always)]
+    fn mode(self, index: usize) -> Mode {
+        match self.modes >> (2 * index) & 3 {
+            0 => Mode::Position,
+            1 => Mode::Immediate,
+            _ => Mode::Relative,
+        }
+    }
+
+    /// Packs the decode with the raw instruction it came from into a slot of `Code`, unless the
+    /// instruction is too large to fit
+    fn pack(self, raw: i64) -> Option<u32> {
+        if !(0..i64::from(u16::MAX)).contains(&raw) {
+            return None;
+        }
+        let op = Op::ALL.iter().position(|&op| op == self.op).unwrap_or(0) as u32;
+        Some((raw as u32 + 1) | op << 16 | self.modes << 20)
+    }
+
+    /// The decode held in a slot of `Code`, if it was packed from the given raw instruction
+    #[inline(always)]
+    fn unpack(slot: u32, raw: i64) -> Option<Self> {
+        let key = slot & 0xffff;
+        if key == 0 || i64::from(key) != raw.wrapping_add(1) {
+            return None;
+        }
+        Some(Self {
+            op: Op::ALL[(slot >> 16 & 0xf) as usize % Op::ALL.len()],
+            modes: slot >> 20,
+        })
+    }
+}
+
+/// Instructions decoded so far within a program's original code, shared between the program and
+/// all its clones. Each slot holds the raw instruction alongside its decode, so a slot is simply
+/// ignored wherever memory has since been written with something else.
+#[derive(Debug, Clone)]
+struct Code(Arc<[AtomicU32]>);
+
+impl Code {
+    fn new(len: usize) -> Self {
+        Self((0..len).map(|_| AtomicU32::new(0)).collect())
+    }
+}
+
+/// Why a program stopped running
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RunState {
+    /// Waiting for a value to be input
+    AwaitingInput,
+    /// Halted, so the program will not run any further
+    Halted,
+    /// A value was just output, with the number of values waiting to be read
+    OutputReady(usize),
+    /// Stopped because the solver's deadline has passed
+    Cancelled,
+    /// Stopped after using up the instructions or time it was given, and can be run again
+    OutOfBudget,
+}
+
+/// Fault raised by an Intcode program, with the pointer and raw instruction where it happened
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum IntcodeError {
+    InvalidOpcode {
+        pointer: usize,
+        instruction: i64,
+    },
+    InvalidMode {
+        pointer: usize,
+        instruction: i64,
+        mode: i64,
+    },
+    NegativeAddress {
+        pointer: usize,
+        instruction: i64,
+        address: i64,
+    },
+    JumpOutOfRange {
+        pointer: usize,
+        instruction: i64,
+        target: i64,
+    },
+    MemoryLimit {
+        pointer: usize,
+        instruction: i64,
+        address: usize,
+        limit: usize,
+    },
+    /// The result of an addition or multiplication, or the relative base, doesn't fit in 64 bits
+    Overflow {
+        pointer: usize,
+        instruction: i64,
+    },
+}
+
+impl IntcodeError {
+    pub fn pointer(&self) -> usize {
+        match *self {
+            Self::InvalidOpcode { pointer, .. }
+            | Self::InvalidMode { pointer, .. }
+            | Self::NegativeAddress { pointer, .. }
+            | Self::JumpOutOfRange { pointer, .. }
+            | Self::MemoryLimit { pointer, .. }
+            | Self::Overflow { pointer, .. } => pointer,
+        }
+    }
+
+    pub fn instruction(&self) -> i64 {
+        match *self {
+            Self::InvalidOpcode { instruction, .. }
+            | Self::InvalidMode { instruction, .. }
+            | Self::NegativeAddress { instruction, .. }
+            | Self::JumpOutOfRange { instruction, .. }
+            | Self::MemoryLimit { instruction, .. }
+            | Self::Overflow { instruction, .. } => instruction,
+        }
+    }
+}
+
+impl Display for IntcodeError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            Self::InvalidOpcode { .. } => write!(f, "invalid opcode {}", self.instruction() % 100),
+            Self::InvalidMode { mode, .. } => write!(f, "invalid parameter mode {}", mode),
+            Self::NegativeAddress { address, .. } => write!(f, "negative address {}", address),
+            Self::JumpOutOfRange { target, .. } => write!(f, "jump to {} out of range", target),
+            Self::MemoryLimit { address, limit, .. } => write!(
+                f,
+                "write to {} exceeds memory limit of {} values",
+                address, limit
+            ),
+            Self::Overflow { .. } => write!(f, "arithmetic overflow"),
+        }?;
+        write!(
+            f,
+            " in instruction {} at {}",
+            self.instruction(),
+            self.pointer()
+        )
+    }
+}
+
+impl Error for IntcodeError {}
+
+/// What stopped a program from executing further
+enum Event {
+    Output,
+    Stopped(RunState),
+}
+
+#[derive(Debug, Clone)]
+pub struct Intcode {
+    state: Memory,
+    code: Code,
+    queues: Queues,
+    base: i64,
+    pointer: usize,
+    finished: bool,
+    trace: Option<Trace>,
+    profile: Option<SharedProfile>,
+}
+
+impl Intcode {
+    pub fn new(state: Vec<i64>) -> Self {
+        Self {
+            code: Code::new(state.len()),
+            state: Memory::new(state),
+            queues: Queues::default(),
+            base: 0,
+            pointer: 0,
+            finished: false,
+            trace: None,
+            profile: profile::current(),
+        }
+    }
+
+    #[inline(always)]
+    fn get(&self, index: usize) -> i64 {
+        self.state.get(index)
+    }
+
+    #[inline(always)]
+    fn current(&self) -> i64 {
+        self.get(self.pointer)
+    }
+
+    fn decode(&self) -> Result<Decoded, IntcodeError> {
+        Decoded::new(self.current(), self.pointer)
+    }
+
+    /// Decodes the current instruction, reusing the decode from any earlier time the same
+    /// instruction was executed at this address by the program or one of its clones
+    #[inline(always)]
+    fn fetch(&self) -> Result<Decoded, IntcodeError> {
+        let raw = self.current();
+        let slot = match self.code.0.get(self.pointer) {
+            Some(slot) => slot,
+            None => return Decoded::new(raw, self.pointer),
+        };
+        if let Some(decoded) = Decoded::unpack(slot.load(Ordering::Relaxed), raw) {
+            return Ok(decoded);
+        }
+        let decoded = Decoded::new(raw, self.pointer)?;
+        if let Some(packed) = decoded.pack(raw) {
+            slot.store(packed, Ordering::Relaxed);
+        }
+        Ok(decoded)
+    }
+
+    /// Address of a parameter in position or relative mode
+    #[inline(always)]
+    fn address(&self, value: i64) -> Result<usize, IntcodeError> {
+        if value < 0 {
+            Err(self.negative_address(value))
+        } else {
+            Ok(value as usize)
+        }
+    }
+
+    #[cold]
+    fn negative_address(&self, address: i64) -> IntcodeError {
+        IntcodeError::NegativeAddress {
+            pointer: self.pointer,
+            instruction: self.current(),
+            address,
+        }
+    }
+
+    /// Result of arithmetic by the current instruction, or an error if it overflowed
+    #[inline(always)]
+    fn checked(&self, result: Option<i64>) -> Result<i64, IntcodeError> {
+        match result {
+            Some(value) => Ok(value),
+            None => Err(self.overflow()),
+        }
+    }
+
+    #[cold]
+    fn overflow(&self) -> IntcodeError {
+        IntcodeError::Overflow {
+            pointer: self.pointer,
+            instruction: self.current(),
+        }
+    }
+
+    #[inline(always)]
+    fn read(&self, decoded: &Decoded, index: usize) -> Result<i64, IntcodeError> {
+        let value = self.get(self.pointer + 1 + index);
+        Ok(match decoded.mode(index) {
+            Mode::Immediate => value,
+            Mode::Position => self.get(self.address(value)?),
+            Mode::Relative => {
+                let address = self.checked(value.checked_add(self.base))?;
+                self.get(self.address(address)?)
+            }
+        })
+    }
+
+    /// Address written by a parameter
+    #[inline(always)]
+    fn target(&self, decoded: &Decoded, index: usize) -> Result<usize, IntcodeError> {
+        let value = self.get(self.pointer + 1 + index);
+        match decoded.mode(index) {
+            Mode::Relative => self.address(self.checked(value.checked_add(self.base))?),
+            _ => self.address(value),
+        }
+    }
+
+    #[cold]
+    fn limit_error(&self, address: usize, err: LimitExceeded) -> IntcodeError {
+        IntcodeError::MemoryLimit {
+            pointer: self.pointer,
+            instruction: self.current(),
+            address,
+            limit: err.limit,
+        }
+    }
+
+    #[inline(always)]
+    fn write(&mut self, decoded: &Decoded, index: usize, value: i64) -> Result<(), IntcodeError> {
+        let address = self.target(decoded, index)?;
+        self.state
+            .set(address, value)
+            .map_err(|e| self.limit_error(address, e))?;
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn jump(&mut self, target: i64) -> Result<(), IntcodeError> {
+        if target < 0 || target as usize >= self.state.len() {
+            return Err(self.jump_error(target));
+        }
+        self.pointer = target as usize;
+        Ok(())
+    }
+
+    #[cold]
+    fn jump_error(&self, target: i64) -> IntcodeError {
+        IntcodeError::JumpOutOfRange {
+            pointer: self.pointer,
+            instruction: self.current(),
+            target,
+        }
+    }
+
+    /// Reads a value into memory, checking the write can succeed before taking the value
+    fn write_input<T: IntcodeIo>(
+        &mut self,
+        decoded: &Decoded,
+        io: &mut T,
+    ) -> Result<Option<Event>, IntcodeError> {
+        let address = self.target(decoded, 0)?;
+        self.state
+            .check(address)
+            .map_err(|e| self.limit_error(address, e))?;
+        match io.input() {
+            Some(value) => {
+                self.write(decoded, 0, value)?;
+                self.pointer += 2;
+                Ok(None)
+            }
+            None => Ok(Some(Event::Stopped(RunState::AwaitingInput))),
+        }
+    }
+
+    /// Sets a value in memory, regardless of any memory limit
+    pub fn set_value(&mut self, position: usize, value: i64) {
+        self.state.force(position, value);
+    }
+
+    /// Value at an address, where memory never written reads as zero
+    pub fn value(&self, position: usize) -> i64 {
+        self.state.get(position)
+    }
+
+    /// The program's memory from address zero, including any written beyond its original length.
+    /// Values written far beyond the program are stored separately and left out.
+    pub fn memory(&self) -> &[i64] {
+        self.state.image()
+    }
+
+    /// Caps the number of memory values the program may use, so a write needing more raises
+    /// an error. Memory is unlimited by default.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.state.set_limit(limit);
+    }
+
+    /// Address of the next instruction to execute
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    /// Relative base used by parameters in relative mode
+    pub fn base(&self) -> i64 {
+        self.base
+    }
+
+    /// Values input but not yet read by the program
+    pub fn input(&self) -> &VecDeque<i64> {
+        &self.queues.input
+    }
+
+    /// Values output but not yet taken
+    pub fn output(&self) -> &VecDeque<i64> {
+        &self.queues.output
+    }
+
+    /// Writes a line to a trace for every instruction executed, or stops tracing
+    pub fn set_trace(&mut self, trace: Option<Trace>) {
+        self.trace = trace;
+    }
+
+    /// Counts instructions executed in a profile, or stops profiling. Programs start with the
+    /// profile being recorded on the current thread, if any.
+    pub fn set_profile(&mut self, profile: Option<SharedProfile>) {
+        self.profile = profile;
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.queues.input.push_back(value);
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = i64> + '_ {
+        self.queues.output.drain(..)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Runs until the program halts or needs more input. Also stops early if the solver's
+    /// deadline has passed. A faulting instruction is left unexecuted, so running again raises
+    /// the same error.
+    pub fn run<I>(&mut self, input: I) -> Result<RunState, IntcodeError>
+    where
+        I: Iterator<Item = i64>,
+    {
+        self.queues.input.extend(input);
+        self.with_queues(|program, queues| program.run_with(queues))
+    }
+
+    /// Runs like `run`, but also stops after each value is output
+    pub fn run_until_output<I>(&mut self, input: I) -> Result<RunState, IntcodeError>
+    where
+        I: Iterator<Item = i64>,
+    {
+        self.queues.input.extend(input);
+        let event = self.with_queues(|program, queues| program.execute(queues))?;
+        Ok(self.run_state(event))
+    }
+
+    /// Runs like `run`, but reading and writing values through the given I/O rather than the
+    /// program's own queues. The program only stops for input when the I/O has none to give.
+    pub fn run_with<T: IntcodeIo>(&mut self, io: &mut T) -> Result<RunState, IntcodeError> {
+        loop {
+            if let Event::Stopped(state) = self.execute(io)? {
+                return Ok(state);
+            }
+        }
+    }
+
+    /// Runs like `run`, but stops once the given number of instructions have been executed
+    pub fn run_with_budget(&mut self, instructions: usize) -> Result<RunState, IntcodeError> {
+        self.with_queues(|program, queues| program.run_limited(queues, Some(instructions), None))
+    }
+
+    /// Runs like `run`, but stops once the given time has passed
+    pub fn run_for(&mut self, duration: Duration) -> Result<RunState, IntcodeError> {
+        let deadline = Instant::now() + duration;
+        self.with_queues(|program, queues| program.run_limited(queues, None, Some(deadline)))
+    }
+
+    /// Runs like `run_with_budget`, but through the given I/O like `run_with`
+    pub fn run_io_with_budget<T: IntcodeIo>(
+        &mut self,
+        io: &mut T,
+        instructions: usize,
+    ) -> Result<RunState, IntcodeError> {
+        self.run_limited(io, Some(instructions), None)
+    }
+
+    /// Runs like `run_for`, but through the given I/O like `run_with`
+    pub fn run_io_for<T: IntcodeIo>(
+        &mut self,
+        io: &mut T,
+        duration: Duration,
+    ) -> Result<RunState, IntcodeError> {
+        self.run_limited(io, None, Some(Instant::now() + duration))
+    }
+
+    /// Runs until the program stops, or until either limit is reached. Like the solver's
+    /// deadline, the time is only checked on starting and after jumping backwards.
+    fn run_limited<T: IntcodeIo>(
+        &mut self,
+        io: &mut T,
+        instructions: Option<usize>,
+        deadline: Option<Instant>,
+    ) -> Result<RunState, IntcodeError> {
+        let expired = |deadline: Option<Instant>| deadline.is_some_and(|d| Instant::now() >= d);
+        let mut remaining = instructions;
+        let mut checked = true;
+        while !self.finished {
+            if checked && cancel::is_cancelled() {
+                return Ok(RunState::Cancelled);
+            }
+            if remaining == Some(0) || (checked && expired(deadline)) {
+                return Ok(RunState::OutOfBudget);
+            }
+            let pointer = self.pointer;
+            if let Some(Event::Stopped(state)) = self.step_with(io)? {
+                return Ok(state);
+            }
+            remaining = remaining.map(|r| r - 1);
+            checked = self.pointer <= pointer;
+        }
+        Ok(RunState::Halted)
+    }
+
+    /// Calls a function with the program's queues taken out of it, so both can be borrowed
+    fn with_queues<R>(&mut self, f: impl FnOnce(&mut Self, &mut Queues) -> R) -> R {
+        let mut queues = take(&mut self.queues);
+        let result = f(self, &mut queues);
+        self.queues = queues;
+        result
+    }
+
+    fn run_state(&self, event: Event) -> RunState {
+        match event {
+            Event::Output => RunState::OutputReady(self.queues.output.len()),
+            Event::Stopped(state) => state,
+        }
+    }
+
+    /// Executes instructions until a value is output or the program stops. The deadline is only
+    /// checked on starting and after jumping backwards, as a program can't loop without doing so.
+    fn execute<T: IntcodeIo>(&mut self, io: &mut T) -> Result<Event, IntcodeError> {
+        let plain = self.trace.is_none() && self.profile.is_none();
+        let mut cancelled = cancel::is_cancelled();
+        while !self.finished {
+            if cancelled {
+                return Ok(Event::Stopped(RunState::Cancelled));
+            }
+            let pointer = self.pointer;
+            let event = if plain {
+                self.execute_instruction(io)?
+            } else {
+                self.step_with(io)?
+            };
+            if let Some(event) = event {
+                return Ok(event);
+            }
+            cancelled = self.pointer <= pointer && cancel::is_cancelled();
+        }
+        Ok(Event::Stopped(RunState::Halted))
+    }
+
+    /// Executes a single instruction, returning the state if it stopped the program or output a
+    /// value. Nothing is executed while the program awaits input or has halted.
+    pub fn step(&mut self) -> Result<Option<RunState>, IntcodeError> {
+        let event = self.with_queues(|program, queues| program.step_with(queues))?;
+        Ok(event.map(|e| self.run_state(e)))
+    }
+
+    fn step_with<T: IntcodeIo>(&mut self, io: &mut T) -> Result<Option<Event>, IntcodeError> {
+        if self.finished {
+            return Ok(Some(Event::Stopped(RunState::Halted)));
+        }
+        if self.trace.is_none() && self.profile.is_none() {
+            return self.execute_instruction(io);
+        }
+
+        let pointer = self.pointer;
+        let opcode = self.current() % 100;
+        let traced = self.trace.as_ref().map(|_| {
+            let text = Instruction::decode(self.memory(), pointer)
+                .map_or_else(|| format!("DATA {}", self.current()), |i| i.to_string());
+            let (values, written) = self.operands();
+            (text, values, written)
+        });
+        let result = self.execute_instruction(io);
+        if let Err(_) | Ok(Some(Event::Stopped(RunState::AwaitingInput))) = result {
+            return result;
+        }
+
+        if let Some(Ok(mut profile)) = self.profile.as_ref().map(|p| p.lock()) {
+            profile.count(pointer, opcode);
+        }
+        if let (Some(trace), Some((text, values, written))) = (&self.trace, traced) {
+            let values = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+            let mut details = values.join(", ");
+            if let Some(address) = written {
+                details += &format!(" -> [{}] = {}", address, self.value(address));
+            }
+            let line = format!("{:>6}: {:<28} ; {}", pointer, text, details.trim_start());
+            trace.write_line(line.trim_end_matches([' ', ';']));
+        }
+        result
+    }
+
+    /// Values of the operands read by the current instruction, and the address it writes, if any
+    fn operands(&self) -> (Vec<i64>, Option<usize>) {
+        let decoded = match self.decode() {
+            Ok(decoded) => decoded,
+            Err(_) => return (vec![], None),
+        };
+        let written = match decoded.op {
+            Op::Add | Op::Mul | Op::Lt | Op::Eq | Op::In => decoded.op.arity() - 1,
+            _ => 3,
+        };
+        let values = (0..decoded.op.arity())
+            .filter(|&i| i != written)
+            .filter_map(|i| self.read(&decoded, i).ok())
+            .collect();
+        let address = Some(written)
+            .filter(|&i| i < decoded.op.arity())
+            .and_then(|i| self.target(&decoded, i).ok());
+        (values, address)
+    }
+
+    #[inline(always)]
+    fn execute_instruction<T: IntcodeIo>(
+        &mut self,
+        io: &mut T,
+    ) -> Result<Option<Event>, IntcodeError> {
+        let decoded = self.fetch()?;
+        let d = &decoded;
+        match d.op {
+            Op::Add => {
+                let sum = self.checked(self.read(d, 0)?.checked_add(self.read(d, 1)?))?;
+                self.write(d, 2, sum)?;
+                self.pointer += 4;
+            }
+            Op::Mul => {
+                let product = self.checked(self.read(d, 0)?.checked_mul(self.read(d, 1)?))?;
+                self.write(d, 2, product)?;
+                self.pointer += 4;
+            }
+            Op::In => return self.write_input(d, io),
+            Op::Out => {
+                io.output(self.read(d, 0)?);
+                self.pointer += 2;
+                return Ok(Some(Event::Output));
+            }
+            Op::Jt | Op::Jf => {
+                if (self.read(d, 0)? != 0) == (d.op == Op::Jt) {
+                    self.jump(self.read(d, 1)?)?;
+                } else {
+                    self.pointer += 3;
+                }
+            }
+            Op::Lt => {
+                self.write(d, 2, (self.read(d, 0)? < self.read(d, 1)?) as i64)?;
+                self.pointer += 4;
+            }
+            Op::Eq => {
+                self.write(d, 2, (self.read(d, 0)? == self.read(d, 1)?) as i64)?;
+                self.pointer += 4;
+            }
+            Op::Rbo => {
+                self.base = self.checked(self.base.checked_add(self.read(d, 0)?))?;
+                self.pointer += 2;
+            }
+            Op::Hlt => {
+                self.finished = true;
+                return Ok(Some(Event::Stopped(RunState::Halted)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for Intcode {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queues.output.pop_front()
+    }
+}
+
+/// Writes the program's memory as comma-separated values, as read by `from_str`
+impl Display for Intcode {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, value) in self.memory().iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, "{}{}", separator, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Intcode {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let state = s
+            .split(',')
+            .map(|i| i.parse::<i64>())
+            .collect::<Result<Vec<i64>, ParseIntError>>()?;
+        Ok(Self::new(state))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::iter::{empty, once};
+
+    fn run(program: &str) -> Result<RunState, IntcodeError> {
+        program.parse::<Intcode>().unwrap().run(empty())
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            run("1,0,0,0,42"),
+            Err(IntcodeError::InvalidOpcode {
+                pointer: 4,
+                instruction: 42
+            })
+        );
+        assert_eq!(
+            run("301,0,0,0,99"),
+            Err(IntcodeError::InvalidMode {
+                pointer: 0,
+                instruction: 301,
+                mode: 3
+            })
+        );
+        assert_eq!(
+            run("4,-1,99"),
+            Err(IntcodeError::NegativeAddress {
+                pointer: 0,
+                instruction: 4,
+                address: -1
+            })
+        );
+        assert_eq!(
+            run("1105,1,-3"),
+            Err(IntcodeError::JumpOutOfRange {
+                pointer: 0,
+                instruction: 1105,
+                target: -3
+            })
+        );
+        let err = run("109,-5,204,1,99").unwrap_err();
+        assert_eq!(err.pointer(), 2);
+        assert_eq!(
+            err.to_string(),
+            "negative address -4 in instruction 204 at 2"
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            run("1101,9223372036854775807,1,0,99"),
+            Err(IntcodeError::Overflow {
+                pointer: 0,
+                instruction: 1101
+            })
+        );
+        assert_eq!(
+            run("1102,4611686018427387904,-3,0,99")
+                .unwrap_err()
+                .to_string(),
+            "arithmetic overflow in instruction 1102 at 0"
+        );
+        assert_eq!(
+            run("109,9223372036854775807,109,1,99"),
+            Err(IntcodeError::Overflow {
+                pointer: 2,
+                instruction: 109
+            })
+        );
+        assert_eq!(
+            run("109,9223372036854775807,204,1,99").map_err(|e| e.pointer()),
+            Err(2)
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let mut program = "3,0,4,0,99".parse::<Intcode>().unwrap();
+        assert_eq!(program.run(empty()), Ok(RunState::AwaitingInput));
+        assert_eq!(program.run(once(7)), Ok(RunState::Halted));
+        assert_eq!(program.collect::<Vec<i64>>(), vec![7]);
+    }
+
+    #[test]
+    fn test_run_until_output() {
+        let mut program = "104,1,104,2,99".parse::<Intcode>().unwrap();
+        assert_eq!(
+            program.run_until_output(empty()),
+            Ok(RunState::OutputReady(1))
+        );
+        assert_eq!(
+            program.run_until_output(empty()),
+            Ok(RunState::OutputReady(2))
+        );
+        assert_eq!(program.run_until_output(empty()), Ok(RunState::Halted));
+        assert_eq!(program.run(empty()), Ok(RunState::Halted));
+        assert_eq!(program.drain().collect::<Vec<i64>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // Outputs 7, then overwrites that instruction with a halt and jumps back to it
+        let mut program = "104,7,1101,0,99,0,1105,1,0".parse::<Intcode>().unwrap();
+        let mut copy = program.clone();
+        assert_eq!(program.run(empty()), Ok(RunState::Halted));
+        assert_eq!(program.collect::<Vec<i64>>(), vec![7]);
+        // The copy shares decoded instructions but still runs its own unmodified code
+        assert_eq!(copy.run(empty()), Ok(RunState::Halted));
+        assert_eq!(copy.collect::<Vec<i64>>(), vec![7]);
+
+        let mut program = "104,7,99".parse::<Intcode>().unwrap();
+        program.set_value(0, 304);
+        assert_eq!(
+            program.run(empty()),
+            Err(IntcodeError::InvalidMode {
+                pointer: 0,
+                instruction: 304,
+                mode: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_with() {
+        // Doubles each value input until it reads zero
+        let mut program = "3,15,1006,15,14,1002,15,2,15,4,15,1105,1,0,99,0"
+            .parse::<Intcode>()
+            .unwrap();
+        let mut output = Vec::new();
+        let mut inputs = vec![0, 5, 3];
+        let mut io = Handlers {
+            input: || inputs.pop(),
+            output: |value| output.push(value),
+        };
+        assert_eq!(program.run_with(&mut io), Ok(RunState::Halted));
+        assert_eq!(output, vec![6, 10]);
+
+        let mut program = "3,0,4,0,99".parse::<Intcode>().unwrap();
+        let mut queues = Queues::default();
+        assert_eq!(program.run_with(&mut queues), Ok(RunState::AwaitingInput));
+        queues.input.push_back(4);
+        assert_eq!(program.run_with(&mut queues), Ok(RunState::Halted));
+        assert_eq!(queues.output, vec![4]);
+        assert!(program.output().is_empty());
+    }
+
+    #[test]
+    fn test_memory() {
+        let mut program = "21101,3,4,1000000000000,204,1000000000000,99"
+            .parse::<Intcode>()
+            .unwrap();
+        program.run(empty()).unwrap();
+        assert_eq!(program.value(1_000_000_000_000), 7);
+        assert_eq!(program.next(), Some(7));
+        assert_eq!(program.memory().len(), 7);
+        program.set_value(usize::MAX, 8);
+        assert_eq!(program.value(usize::MAX), 8);
+
+        let mut program = "1101,3,4,100000,99".parse::<Intcode>().unwrap();
+        program.set_memory_limit(Some(1000));
+        let err = program.run(empty()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "write to 100000 exceeds memory limit of 1000 values in instruction 1101 at 0"
+        );
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut program = "104,1,1105,1,0".parse::<Intcode>().unwrap();
+        assert_eq!(program.run_with_budget(3), Ok(RunState::OutOfBudget));
+        assert_eq!(program.drain().collect::<Vec<i64>>(), vec![1, 1]);
+        assert_eq!(program.pointer(), 2);
+        assert_eq!(program.run_with_budget(1), Ok(RunState::OutOfBudget));
+        assert_eq!(program.pointer(), 0);
+        assert_eq!(
+            program.run_for(Duration::from_millis(1)),
+            Ok(RunState::OutOfBudget)
+        );
+        assert!(program.drain().count() > 1);
+
+        let mut program = "3,0,99".parse::<Intcode>().unwrap();
+        assert_eq!(program.run_with_budget(5), Ok(RunState::AwaitingInput));
+        program.push_input(1);
+        assert_eq!(program.run_with_budget(2), Ok(RunState::Halted));
+    }
+}
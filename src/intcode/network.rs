@@ -0,0 +1,444 @@
+/// Network of Intcode machines passing packets to each other
+///
+/// Each machine reads its address when it boots, then reads packets as a run of values, or `-1`
+/// when none are waiting. Packets are output as a destination address then a payload of fixed
+/// length. Packets addressed outside the network are handed to a `Router`, which also decides what
+/// happens once every machine has gone idle by reading `-1` several times in a row.
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::{Intcode, IntcodeError, IntcodeIo, RunState};
+use crate::cancel;
+
+/// Layout of packets and when the network counts as idle
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Config {
+    /// Values in each packet after its destination address
+    pub payload: usize,
+    /// Consecutive `-1` reads after which a machine is taken to be idle, at least one
+    pub idle_reads: usize,
+    /// Most recent events kept in the log, where zero turns the log off
+    pub log: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Packet {
+    /// Machine that sent the packet, or `None` for the router
+    pub source: Option<usize>,
+    pub destination: i64,
+    pub payload: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Event {
+    /// Packet output by a machine
+    Sent(Packet),
+    /// Packet sent into the network by the router or `Network::send`
+    Injected(Packet),
+    /// Every machine was idle, so the router was asked what to do
+    Idle,
+    Halted(usize),
+}
+
+/// What the router wants the network to do next
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Action<T> {
+    Wait,
+    Deliver(Packet),
+    /// Stops the network, which returns the value
+    Stop(T),
+    /// The packet can't be routed anywhere, which stops the network with an error
+    Reject,
+}
+
+pub trait Router {
+    type Output;
+
+    /// Takes a packet addressed outside the network
+    fn receive(&mut self, packet: Packet) -> Action<Self::Output>;
+
+    /// Called when every machine is idle. Anything but delivering a packet or stopping leaves the
+    /// network deadlocked.
+    fn idle(&mut self) -> Action<Self::Output>;
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NetworkError {
+    Fault {
+        machine: usize,
+        error: IntcodeError,
+    },
+    /// A machine halted partway through outputting a packet
+    IncompletePacket {
+        machine: usize,
+        values: Vec<i64>,
+    },
+    Unroutable(Packet),
+    Deadlock,
+    Cancelled,
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            NetworkError::Fault { machine, .. } => write!(f, "machine {} faulted", machine),
+            NetworkError::IncompletePacket { machine, values } => write!(
+                f,
+                "machine {} halted with incomplete packet {:?}",
+                machine, values
+            ),
+            NetworkError::Unroutable(packet) => {
+                write!(f, "invalid address {}", packet.destination)
+            }
+            NetworkError::Deadlock => write!(f, "network is idle with nothing to deliver"),
+            NetworkError::Cancelled => write!(f, "network was cancelled"),
+        }
+    }
+}
+
+impl Error for NetworkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NetworkError::Fault { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Interface of a machine to the network
+#[derive(Debug, Clone, Default)]
+struct Nic {
+    incoming: VecDeque<i64>,
+    /// Values output since the last complete packet
+    outgoing: Vec<i64>,
+    packets: Vec<Vec<i64>>,
+    frame: usize,
+    /// Whether the machine has been given `-1` during its current turn
+    polled: bool,
+    empty_reads: usize,
+}
+
+impl IntcodeIo for Nic {
+    fn input(&mut self) -> Option<i64> {
+        match self.incoming.pop_front() {
+            Some(value) => {
+                self.empty_reads = 0;
+                Some(value)
+            }
+            // Let the other machines have a turn rather than polling forever
+            None if self.polled => None,
+            None => {
+                self.polled = true;
+                self.empty_reads += 1;
+                Some(-1)
+            }
+        }
+    }
+
+    fn output(&mut self, value: i64) {
+        self.empty_reads = 0;
+        self.outgoing.push(value);
+        if self.outgoing.len() == self.frame {
+            self.packets.push(self.outgoing.split_off(0));
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Machine {
+    program: Intcode,
+    nic: Nic,
+    halted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Network {
+    machines: Vec<Machine>,
+    config: Config,
+    log: VecDeque<Event>,
+}
+
+impl Network {
+    /// Network of copies of the program, each given its address
+    pub fn new(program: &Intcode, size: usize, config: Config) -> Self {
+        let machines = (0..size)
+            .map(|address| Machine {
+                program: program.clone(),
+                nic: Nic {
+                    incoming: vec![address as i64].into(),
+                    frame: 1 + config.payload,
+                    ..Nic::default()
+                },
+                halted: false,
+            })
+            .collect();
+        Self {
+            machines,
+            config: Config {
+                idle_reads: config.idle_reads.max(1),
+                ..config
+            },
+            log: VecDeque::new(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.machines.len()
+    }
+
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    /// The most recent events on the network, oldest first, if the log is turned on
+    pub fn log(&self) -> &VecDeque<Event> {
+        &self.log
+    }
+
+    fn record(&mut self, event: Event) {
+        if self.config.log == 0 {
+            return;
+        }
+        if self.log.len() == self.config.log {
+            self.log.pop_front();
+        }
+        self.log.push_back(event);
+    }
+
+    pub fn machine(&self, address: usize) -> &Intcode {
+        &self.machines[address].program
+    }
+
+    /// Queues a packet for a machine, as if sent by the router
+    pub fn send(&mut self, packet: Packet) -> Result<(), NetworkError> {
+        let machine = match self.address(packet.destination) {
+            Some(address) => &mut self.machines[address],
+            None => return Err(NetworkError::Unroutable(packet)),
+        };
+        machine.nic.incoming.extend(&packet.payload);
+        machine.nic.empty_reads = 0;
+        self.record(Event::Injected(packet));
+        Ok(())
+    }
+
+    fn address(&self, destination: i64) -> Option<usize> {
+        match destination {
+            d if d >= 0 && (d as usize) < self.size() => Some(d as usize),
+            _ => None,
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.machines.iter().all(|m| {
+            m.halted || (m.nic.incoming.is_empty() && m.nic.empty_reads >= self.config.idle_reads)
+        })
+    }
+
+    /// Passes a packet on to its destination, or to the router if it is outside the network
+    fn route<R: Router>(
+        &mut self,
+        packet: Packet,
+        router: &mut R,
+    ) -> Result<Option<R::Output>, NetworkError> {
+        if self.config.log > 0 {
+            self.record(Event::Sent(packet.clone()));
+        }
+        if let Some(address) = self.address(packet.destination) {
+            let nic = &mut self.machines[address].nic;
+            nic.incoming.extend(&packet.payload);
+            nic.empty_reads = 0;
+            return Ok(None);
+        }
+        match router.receive(packet.clone()) {
+            Action::Wait => Ok(None),
+            Action::Deliver(packet) => self.send(packet).map(|_| None),
+            Action::Stop(value) => Ok(Some(value)),
+            Action::Reject => Err(NetworkError::Unroutable(packet)),
+        }
+    }
+
+    /// Gives a machine a turn, then routes the packets it sent
+    fn turn<R: Router>(
+        &mut self,
+        address: usize,
+        router: &mut R,
+    ) -> Result<Option<R::Output>, NetworkError> {
+        let machine = &mut self.machines[address];
+        machine.nic.polled = false;
+        let state = machine
+            .program
+            .run_with(&mut machine.nic)
+            .map_err(|error| NetworkError::Fault {
+                machine: address,
+                error,
+            })?;
+        let packets = machine.nic.packets.split_off(0);
+        match state {
+            RunState::Cancelled => return Err(NetworkError::Cancelled),
+            RunState::Halted if !machine.nic.outgoing.is_empty() => {
+                return Err(NetworkError::IncompletePacket {
+                    machine: address,
+                    values: machine.nic.outgoing.clone(),
+                });
+            }
+            RunState::Halted => {
+                machine.halted = true;
+                self.record(Event::Halted(address));
+            }
+            RunState::AwaitingInput | RunState::OutputReady(_) | RunState::OutOfBudget => (),
+        }
+
+        for values in packets {
+            let packet = Packet {
+                source: Some(address),
+                destination: values[0],
+                payload: values[1..].to_vec(),
+            };
+            if let Some(value) = self.route(packet, router)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs every machine in turn until the router stops the network
+    pub fn run<R: Router>(&mut self, router: &mut R) -> Result<R::Output, NetworkError> {
+        loop {
+            if cancel::is_cancelled() {
+                return Err(NetworkError::Cancelled);
+            }
+            for address in 0..self.size() {
+                if self.machines[address].halted {
+                    continue;
+                }
+                if let Some(value) = self.turn(address, router)? {
+                    return Ok(value);
+                }
+            }
+            if self.is_idle() {
+                self.record(Event::Idle);
+                match router.idle() {
+                    Action::Deliver(packet) => self.send(packet)?,
+                    Action::Stop(value) => return Ok(value),
+                    Action::Wait | Action::Reject => return Err(NetworkError::Deadlock),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::asm::program;
+
+    /// Machine that passes each packet on to the next address, adding one to its Y value
+    const RELAY: &str = "
+                IN   [address]
+        loop:   IN   [x]
+                EQ   [x], #-1, [empty]
+                JT   [empty], #loop
+                IN   [y]
+                ADD  [address], #1, [next]
+                ADD  [y], #1, [y]
+                OUT  [next]
+                OUT  [x]
+                OUT  [y]
+                JT   #1, #loop
+        address: DATA 0
+        x:      DATA 0
+        y:      DATA 0
+        next:   DATA 0
+        empty:  DATA 0
+    ";
+
+    const CONFIG: Config = Config {
+        payload: 2,
+        idle_reads: 2,
+        log: 100,
+    };
+
+    /// Router that returns the first packet it receives, and otherwise does whatever it was given
+    struct Catcher(Option<Action<Vec<i64>>>);
+
+    impl Router for Catcher {
+        type Output = Vec<i64>;
+
+        fn receive(&mut self, packet: Packet) -> Action<Vec<i64>> {
+            Action::Stop(packet.payload)
+        }
+
+        fn idle(&mut self) -> Action<Vec<i64>> {
+            self.0.take().unwrap_or(Action::Wait)
+        }
+    }
+
+    fn packet(destination: i64, payload: Vec<i64>) -> Packet {
+        Packet {
+            source: None,
+            destination,
+            payload,
+        }
+    }
+
+    #[test]
+    fn test_relay() {
+        let mut network = Network::new(&program(RELAY).unwrap(), 3, CONFIG);
+        network.send(packet(0, vec![7, 0])).unwrap();
+        assert_eq!(network.run(&mut Catcher(None)), Ok(vec![7, 3]));
+        let sent = network
+            .log()
+            .iter()
+            .filter_map(|e| match e {
+                Event::Sent(p) => Some((p.source, p.destination)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sent, vec![(Some(0), 1), (Some(1), 2), (Some(2), 3)]);
+
+        let config = Config { log: 1, ..CONFIG };
+        let mut network = Network::new(&program(RELAY).unwrap(), 3, config);
+        network.send(packet(0, vec![7, 0])).unwrap();
+        network.run(&mut Catcher(None)).unwrap();
+        assert_eq!(network.log().len(), 1);
+        assert!(matches!(&network.log()[0], Event::Sent(p) if p.destination == 3));
+
+        let config = Config { log: 0, ..CONFIG };
+        let mut network = Network::new(&program(RELAY).unwrap(), 3, config);
+        network.send(packet(0, vec![7, 0])).unwrap();
+        network.run(&mut Catcher(None)).unwrap();
+        assert!(network.log().is_empty());
+    }
+
+    #[test]
+    fn test_idle() {
+        let mut network = Network::new(&program(RELAY).unwrap(), 2, CONFIG);
+        let mut router = Catcher(Some(Action::Deliver(packet(1, vec![1, 1]))));
+        assert_eq!(network.run(&mut router), Ok(vec![1, 2]));
+        assert_eq!(network.log()[0], Event::Idle);
+
+        let mut network = Network::new(&program(RELAY).unwrap(), 2, CONFIG);
+        assert_eq!(network.run(&mut Catcher(None)), Err(NetworkError::Deadlock));
+        assert_eq!(
+            network.send(packet(-1, vec![])),
+            Err(NetworkError::Unroutable(packet(-1, vec![])))
+        );
+    }
+
+    #[test]
+    fn test_payload() {
+        let config = Config {
+            payload: 1,
+            ..CONFIG
+        };
+        let mut network = Network::new(&program("OUT #5\nHLT").unwrap(), 1, config);
+        assert_eq!(
+            network.run(&mut Catcher(None)),
+            Err(NetworkError::IncompletePacket {
+                machine: 0,
+                values: vec![5]
+            })
+        );
+    }
+}
@@ -0,0 +1,266 @@
+/// Chains of Intcode machines, each reading the output of the one before
+///
+/// The chain may be closed into a loop so that the first machine reads the output of the last.
+/// Machines take turns in order, each running until it needs input that hasn't been sent yet,
+/// and every value passed along the chain is kept as a signal.
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::{Intcode, IntcodeError, Queues, RunState};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Signal {
+    /// Stage that output the value
+    pub stage: usize,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StageError {
+    pub stage: usize,
+    pub error: IntcodeError,
+}
+
+impl Display for StageError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "stage {} failed", self.stage)
+    }
+}
+
+impl Error for StageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Stage chosen to stop the pipeline that isn't in it
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct NoSuchStage {
+    pub stage: usize,
+    pub len: usize,
+}
+
+impl Display for NoSuchStage {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "no stage {} in a pipeline of {}", self.stage, self.len)
+    }
+}
+
+impl Error for NoSuchStage {}
+
+#[derive(Debug, Clone)]
+struct Stage {
+    program: Intcode,
+    queues: Queues,
+    state: RunState,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+    feedback: bool,
+    until: usize,
+    history: Vec<Signal>,
+}
+
+impl Pipeline {
+    /// Open chain of the programs, which runs until the last one halts
+    pub fn new(programs: Vec<Intcode>) -> Self {
+        let until = programs.len().saturating_sub(1);
+        let stages = programs
+            .into_iter()
+            .map(|program| Stage {
+                program,
+                queues: Queues::default(),
+                state: RunState::AwaitingInput,
+            })
+            .collect();
+        Self {
+            stages,
+            feedback: false,
+            until,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Sets whether the output of the last stage is sent back to the first
+    pub fn set_feedback(&mut self, feedback: bool) {
+        self.feedback = feedback;
+    }
+
+    fn check(&self, stage: usize) -> Result<(), NoSuchStage> {
+        match stage {
+            s if s < self.len() => Ok(()),
+            _ => Err(NoSuchStage {
+                stage,
+                len: self.len(),
+            }),
+        }
+    }
+
+    /// Sets the stage whose halting stops the pipeline
+    pub fn set_until(&mut self, stage: usize) -> Result<(), NoSuchStage> {
+        self.check(stage)?;
+        self.until = stage;
+        Ok(())
+    }
+
+    /// Queues a value to be read by a stage, before anything it is sent by the stage before it
+    pub fn push_input(&mut self, stage: usize, value: i64) -> Result<(), NoSuchStage> {
+        self.check(stage)?;
+        self.stages[stage].queues.input.push_back(value);
+        Ok(())
+    }
+
+    pub fn stage(&self, stage: usize) -> Result<&Intcode, NoSuchStage> {
+        self.check(stage)?;
+        Ok(&self.stages[stage].program)
+    }
+
+    /// Every value output by every stage so far, in the order they were output
+    pub fn history(&self) -> &[Signal] {
+        &self.history
+    }
+
+    /// Values output by one stage so far
+    pub fn signals(&self, stage: usize) -> impl Iterator<Item = i64> + '_ {
+        self.history
+            .iter()
+            .filter(move |s| s.stage == stage)
+            .map(|s| s.value)
+    }
+
+    /// Runs the stages in turn until the chosen stage halts. Returns that stage's state if the
+    /// pipeline stops before then, which is when a whole round of turns outputs nothing.
+    pub fn run(&mut self) -> Result<RunState, StageError> {
+        if self.is_empty() {
+            return Ok(RunState::Halted);
+        }
+        loop {
+            let mut progress = false;
+            for i in 0..self.len() {
+                let stage = &mut self.stages[i];
+                if stage.state == RunState::Halted {
+                    continue;
+                }
+                stage.state = stage
+                    .program
+                    .run_with(&mut stage.queues)
+                    .map_err(|error| StageError { stage: i, error })?;
+                let state = stage.state;
+                let output = stage.queues.output.drain(..).collect::<Vec<i64>>();
+                progress |= !output.is_empty();
+                self.history
+                    .extend(output.iter().map(|&value| Signal { stage: i, value }));
+
+                let next = match i + 1 {
+                    n if n < self.len() => Some(n),
+                    _ if self.feedback => Some(0),
+                    _ => None,
+                };
+                if let Some(next) = next {
+                    self.stages[next].queues.input.extend(output);
+                }
+                match state {
+                    RunState::Cancelled => return Ok(state),
+                    RunState::Halted if i == self.until => return Ok(state),
+                    _ => (),
+                }
+            }
+            if !progress {
+                return Ok(self.stages[self.until].state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Program that adds its phase to each value it reads, halting after reading zero
+    const ADDER: &str = "3,14,3,15,1,14,15,16,4,16,1005,15,2,99,0,0,0";
+
+    fn pipeline(phases: &[i64]) -> Pipeline {
+        let program = ADDER.parse::<Intcode>().unwrap();
+        let mut pipeline = Pipeline::new(vec![program; phases.len()]);
+        for (stage, &phase) in phases.iter().enumerate() {
+            pipeline.push_input(stage, phase).unwrap();
+        }
+        pipeline
+    }
+
+    #[test]
+    fn test_chain() {
+        let mut chain = pipeline(&[1, 2, 3]);
+        chain.push_input(0, 10).unwrap();
+        assert_eq!(chain.run(), Ok(RunState::AwaitingInput));
+        assert_eq!(
+            chain.history(),
+            &[
+                Signal {
+                    stage: 0,
+                    value: 11
+                },
+                Signal {
+                    stage: 1,
+                    value: 13
+                },
+                Signal {
+                    stage: 2,
+                    value: 16
+                }
+            ]
+        );
+        chain.push_input(0, -3).unwrap();
+        assert_eq!(chain.run(), Ok(RunState::Halted));
+        assert_eq!(chain.signals(2).collect::<Vec<i64>>(), vec![16, 3]);
+    }
+
+    #[test]
+    fn test_feedback() {
+        let mut ring = pipeline(&[-1, -1]);
+        ring.set_feedback(true);
+        ring.push_input(0, 3).unwrap();
+        assert_eq!(ring.run(), Ok(RunState::Halted));
+        assert_eq!(ring.signals(0).collect::<Vec<i64>>(), vec![2, 0]);
+        assert_eq!(ring.signals(1).collect::<Vec<i64>>(), vec![1, -1]);
+
+        let mut ring = pipeline(&[-1, -1]);
+        ring.set_feedback(true);
+        ring.set_until(0).unwrap();
+        ring.push_input(0, 4).unwrap();
+        assert_eq!(ring.run(), Ok(RunState::Halted));
+        assert_eq!(ring.signals(0).collect::<Vec<i64>>(), vec![3, 1, -1]);
+        assert_eq!(ring.signals(1).collect::<Vec<i64>>(), vec![2, 0]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut chain = Pipeline::new(vec!["99".parse().unwrap(), "5".parse().unwrap()]);
+        assert_eq!(chain.run().unwrap_err().stage, 1);
+
+        assert_eq!(chain.set_until(2), Err(NoSuchStage { stage: 2, len: 2 }));
+        assert_eq!(
+            chain.push_input(2, 0),
+            Err(NoSuchStage { stage: 2, len: 2 })
+        );
+        assert!(chain.stage(1).is_ok());
+        assert_eq!(
+            chain.stage(5).unwrap_err(),
+            NoSuchStage { stage: 5, len: 2 }
+        );
+        assert_eq!(
+            Pipeline::new(vec![]).set_until(0).unwrap_err().to_string(),
+            "no stage 0 in a pipeline of 0"
+        );
+    }
+}
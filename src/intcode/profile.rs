@@ -0,0 +1,192 @@
+/// Execution traces and profiles of Intcode programs
+///
+/// Both are shared between a program and its clones, so a solver that clones a program for every
+/// probe gets a single profile covering all of them. Programs created while a profile is being
+/// recorded on the current thread add to it.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use super::disasm::Op;
+
+/// Addresses listed by the summary of a profile
+const HOTTEST: usize = 10;
+
+thread_local! {
+    static CURRENT: RefCell<Option<SharedProfile>> = const { RefCell::new(None) };
+}
+
+/// Counts of what programs did while running
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Profile {
+    pub instructions: u64,
+    /// Instructions executed for each opcode
+    pub opcodes: BTreeMap<i64, u64>,
+    /// Instructions executed at each address
+    pub addresses: HashMap<usize, u64>,
+    pub inputs: u64,
+    pub outputs: u64,
+}
+
+pub type SharedProfile = Arc<Mutex<Profile>>;
+
+impl Profile {
+    pub fn shared() -> SharedProfile {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    pub(super) fn count(&mut self, pointer: usize, opcode: i64) {
+        self.instructions += 1;
+        *self.opcodes.entry(opcode).or_insert(0) += 1;
+        *self.addresses.entry(pointer).or_insert(0) += 1;
+        match opcode {
+            3 => self.inputs += 1,
+            4 => self.outputs += 1,
+            _ => (),
+        }
+    }
+
+    /// Addresses executed most often with their counts, most frequent first
+    pub fn hottest(&self, count: usize) -> Vec<(usize, u64)> {
+        let mut addresses = self
+            .addresses
+            .iter()
+            .map(|(&a, &c)| (a, c))
+            .collect::<Vec<(usize, u64)>>();
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        addresses.truncate(count);
+        addresses
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        writeln!(
+            f,
+            "instructions: {}, inputs: {}, outputs: {}",
+            self.instructions, self.inputs, self.outputs
+        )?;
+        let percent = |count: u64| 100.0 * count as f64 / self.instructions.max(1) as f64;
+        let opcodes = self
+            .opcodes
+            .iter()
+            .map(|(&code, &count)| {
+                let name = Op::from_code(code).map_or("?", |op| op.mnemonic());
+                format!("{} {:.1}%", name, percent(count))
+            })
+            .collect::<Vec<String>>();
+        writeln!(f, "opcodes: {}", opcodes.join(", "))?;
+        let hottest = self
+            .hottest(HOTTEST)
+            .iter()
+            .map(|&(address, count)| format!("{} ({})", address, count))
+            .collect::<Vec<String>>();
+        write!(f, "hottest addresses: {}", hottest.join(", "))
+    }
+}
+
+/// Runs a function, recording a profile of every program created on this thread meanwhile
+pub fn record<T>(f: impl FnOnce() -> T) -> (T, Profile) {
+    let profile = Profile::shared();
+    let result = with_profile(Some(profile.clone()), f);
+    let profile = profile.lock().map(|p| p.clone()).unwrap_or_default();
+    (result, profile)
+}
+
+/// Runs a function with programs created on this thread adding to the given profile, restoring
+/// the previous one afterwards
+pub fn with_profile<T>(profile: Option<SharedProfile>, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT.with(|c| c.replace(profile));
+    let result = f();
+    CURRENT.with(|c| c.replace(previous));
+    result
+}
+
+/// Profile being recorded on this thread, if any
+pub fn current() -> Option<SharedProfile> {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+/// Destination for the trace of a program, with one line per instruction executed
+#[derive(Clone)]
+pub struct Trace(Arc<Mutex<dyn Write + Send>>);
+
+impl Trace {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(writer)))
+    }
+
+    pub(super) fn write_line(&self, line: &str) {
+        if let Ok(mut writer) = self.0.lock() {
+            // A broken trace shouldn't stop the program being traced
+            writeln!(writer, "{}", line).ok();
+        }
+    }
+}
+
+impl Debug for Trace {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "Trace")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::asm::program;
+    use crate::intcode::Intcode;
+    use std::iter::once;
+
+    /// Writer that can be read back after being handed to a trace
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_trace() {
+        let buffer = Buffer::default();
+        let mut program = "3,9,1002,9,3,9,4,9,99,0".parse::<Intcode>().unwrap();
+        program.set_trace(Some(Trace::new(buffer.clone())));
+        program.run(once(7)).unwrap();
+        let trace = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            trace,
+            "     0: IN   [9]                     ; -> [9] = 7\n     \
+                  2: MUL  [9], #3, [9]            ; 7, 3 -> [9] = 21\n     \
+                  6: OUT  [9]                     ; 21\n     \
+                  8: HLT\n"
+        );
+    }
+
+    #[test]
+    fn test_record() {
+        let source = "loop: IN [x]\nOUT [x]\nJT #1, #loop\nx: DATA 0";
+        let (output, profile) = record(|| {
+            let program = program(source).unwrap();
+            (1..=3)
+                .map(|i| {
+                    let mut clone = program.clone();
+                    clone.run(once(i)).unwrap();
+                    clone.next().unwrap()
+                })
+                .sum::<i64>()
+        });
+        assert_eq!(output, 6);
+        assert_eq!(profile.instructions, 9);
+        assert_eq!((profile.inputs, profile.outputs), (3, 3));
+        assert_eq!(profile.opcodes[&5], 3);
+        assert_eq!(profile.hottest(1), vec![(0, 3)]);
+        assert!(current().is_none());
+    }
+}
@@ -1,16 +1,19 @@
-/// The Intcode computer used by several problems
+/// The Intcode computer as it was before it reported errors and kept decoded instructions
+///
+/// This is kept unchanged, other than its name, as the baseline for benchmarks and as a check on
+/// `Intcode`. It panics on anything invalid, and writes grow its memory as a plain vector.
 use std::collections::VecDeque;
 use std::num::ParseIntError;
 use std::str::FromStr;
 
 enum Mode {
-    IMMEDIATE,
-    POSITION,
-    RELATIVE,
+    Immediate,
+    Position,
+    Relative,
 }
 
 #[derive(Debug, Clone)]
-pub struct Intcode {
+pub struct Reference {
     state: Vec<i64>,
     input: VecDeque<i64>,
     output: VecDeque<i64>,
@@ -19,7 +22,7 @@ pub struct Intcode {
     finished: bool,
 }
 
-impl Intcode {
+impl Reference {
     pub fn new(state: Vec<i64>) -> Self {
         Self {
             state,
@@ -38,9 +41,9 @@ impl Intcode {
     fn read_mode(&self, position: usize, index: usize) -> Mode {
         let modes = self.state[position] / 100;
         match (modes / 10_i64.pow(index as u32)) % 10 {
-            0 => Mode::POSITION,
-            1 => Mode::IMMEDIATE,
-            2 => Mode::RELATIVE,
+            0 => Mode::Position,
+            1 => Mode::Immediate,
+            2 => Mode::Relative,
             m => panic!(
                 "{} within opcode {} is not a valid parameter.",
                 m, self.state[position]
@@ -52,17 +55,17 @@ impl Intcode {
         let value = self.state[position + offset];
         let rel_value = value + self.base;
         match self.read_mode(position, offset - 1) {
-            Mode::IMMEDIATE => value,
-            Mode::POSITION if value >= 0 && (value as usize) < self.state.len() => {
+            Mode::Immediate => value,
+            Mode::Position if value >= 0 && (value as usize) < self.state.len() => {
                 self.state[value as usize]
             }
-            Mode::POSITION if value >= 0 => 0,
-            Mode::POSITION => panic!("Integer {} is to be used as index but is negative.", value),
-            Mode::RELATIVE if rel_value >= 0 && (rel_value as usize) < self.state.len() => {
+            Mode::Position if value >= 0 => 0,
+            Mode::Position => panic!("Integer {} is to be used as index but is negative.", value),
+            Mode::Relative if rel_value >= 0 && (rel_value as usize) < self.state.len() => {
                 self.state[rel_value as usize]
             }
-            Mode::RELATIVE if rel_value >= 0 => 0,
-            Mode::RELATIVE => panic!(
+            Mode::Relative if rel_value >= 0 => 0,
+            Mode::Relative => panic!(
                 "Integer {} + base {} is to be used as index but is negative.",
                 value, self.base
             ),
@@ -71,7 +74,7 @@ impl Intcode {
 
     fn write(&mut self, position: usize, offset: usize, value: i64) {
         let index_s = match self.read_mode(position, offset - 1) {
-            Mode::RELATIVE => self.state[position + offset] + self.base,
+            Mode::Relative => self.state[position + offset] + self.base,
             _ => self.state[position + offset],
         };
         if index_s < 0 {
@@ -188,7 +191,7 @@ impl Intcode {
             match self.current() % 100 {
                 1 => self.add(),
                 2 => self.multiply(),
-                3 if self.input.len() == 0 => break,
+                3 if self.input.is_empty() => break,
                 3 => self.write_input(),
                 4 => self.read_output(),
                 5 => self.jump_if_true(),
@@ -208,7 +211,7 @@ impl Intcode {
     }
 }
 
-impl Iterator for Intcode {
+impl Iterator for Reference {
     type Item = i64;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -216,7 +219,7 @@ impl Iterator for Intcode {
     }
 }
 
-impl FromStr for Intcode {
+impl FromStr for Reference {
     type Err = ParseIntError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
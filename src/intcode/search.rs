@@ -0,0 +1,226 @@
+/// Search for the inputs that make a program produce a given output
+///
+/// The program is given a noun at address 1 and a verb at address 2, and its output is the value
+/// at address 0 once it halts. Programs that only add and multiply by constants output an affine
+/// function of the noun and verb, which is worked out from a few runs and solved directly.
+/// Anything else is searched exhaustively, spread across threads.
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{available_parallelism, scope};
+
+use super::{Intcode, IntcodeError, Queues, RunState};
+use crate::cancel;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SearchError {
+    Fault {
+        noun: i64,
+        verb: i64,
+        error: IntcodeError,
+    },
+    /// The program stopped without halting, such as by asking for input or being cancelled
+    Stopped {
+        noun: i64,
+        verb: i64,
+        state: RunState,
+    },
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            SearchError::Fault { noun, verb, .. } => {
+                write!(f, "program failed with noun {} and verb {}", noun, verb)
+            }
+            SearchError::Stopped { noun, verb, state } => write!(
+                f,
+                "program stopped with {:?} instead of halting, with noun {} and verb {}",
+                state, noun, verb
+            ),
+        }
+    }
+}
+
+impl Error for SearchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SearchError::Fault { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Runs a copy of the program with the noun and verb, returning the value left at address 0
+pub fn output(program: &Intcode, noun: i64, verb: i64) -> Result<i64, SearchError> {
+    let mut program = program.clone();
+    program.set_value(1, noun);
+    program.set_value(2, verb);
+    match program.run_with(&mut Queues::default()) {
+        Ok(RunState::Halted) => Ok(program.value(0)),
+        Ok(state) => Err(SearchError::Stopped { noun, verb, state }),
+        Err(error) => Err(SearchError::Fault { noun, verb, error }),
+    }
+}
+
+/// Finds a noun and verb in the ranges that make the program output the target. If there are
+/// several, the one with the lowest verb is returned, then the lowest noun for that verb.
+pub fn find_inputs(
+    program: &Intcode,
+    target: i64,
+    nouns: Range<i64>,
+    verbs: Range<i64>,
+) -> Result<Option<(i64, i64)>, SearchError> {
+    if nouns.is_empty() || verbs.is_empty() {
+        return Ok(None);
+    }
+    if let Some(inputs) = solve_affine(program, target, &nouns, &verbs)? {
+        return Ok(Some(inputs));
+    }
+    search(program, target, &nouns, &verbs)
+}
+
+/// Fits an affine function of the noun and verb to the program's output, checks it against a few
+/// more runs, then solves it for the target. Returns `None` if the output doesn't fit, or if no
+/// solution is found, as the check may have missed something.
+fn solve_affine(
+    program: &Intcode,
+    target: i64,
+    nouns: &Range<i64>,
+    verbs: &Range<i64>,
+) -> Result<Option<(i64, i64)>, SearchError> {
+    if nouns.end - nouns.start < 2 || verbs.end - verbs.start < 2 {
+        return Ok(None);
+    }
+    let (n0, v0) = (nouns.start, verbs.start);
+    let (n1, v1) = (nouns.end - 1, verbs.end - 1);
+    let base = output(program, n0, v0)?;
+    let per_noun = output(program, n0 + 1, v0)?.wrapping_sub(base);
+    let per_verb = output(program, n0, v0 + 1)?.wrapping_sub(base);
+    let predict = |noun: i64, verb: i64| {
+        per_noun
+            .checked_mul(noun - n0)?
+            .checked_add(per_verb.checked_mul(verb - v0)?)?
+            .checked_add(base)
+    };
+
+    for &(noun, verb) in &[(n0 + 1, v0 + 1), (n1, v0), (n0, v1), (n1, v1)] {
+        if predict(noun, verb) != Some(output(program, noun, verb)?) {
+            return Ok(None);
+        }
+    }
+
+    for verb in verbs.clone() {
+        let remainder = match per_verb
+            .checked_mul(verb - v0)
+            .and_then(|v| target.checked_sub(base)?.checked_sub(v))
+        {
+            Some(remainder) => remainder,
+            None => continue,
+        };
+        let noun = match (per_noun, remainder) {
+            (0, 0) => n0,
+            (0, _) => continue,
+            (p, r) if r % p == 0 => n0 + r / p,
+            _ => continue,
+        };
+        if nouns.contains(&noun) && output(program, noun, verb)? == target {
+            return Ok(Some((noun, verb)));
+        }
+    }
+    Ok(None)
+}
+
+/// Tries every noun and verb, with each thread taking a block of verbs. Threads stop once a
+/// thread with lower verbs has found a solution.
+fn search(
+    program: &Intcode,
+    target: i64,
+    nouns: &Range<i64>,
+    verbs: &Range<i64>,
+) -> Result<Option<(i64, i64)>, SearchError> {
+    let count = (verbs.end - verbs.start) as usize;
+    let threads = available_parallelism().map_or(1, |n| n.get()).min(count);
+    let block = count.div_ceil(threads) as i64;
+    let found = AtomicUsize::new(threads);
+    let deadline = cancel::deadline();
+
+    let scan = |index: usize| -> Result<Option<(i64, i64)>, SearchError> {
+        let start = verbs.start + index as i64 * block;
+        for verb in start..(start + block).min(verbs.end) {
+            if found.load(Ordering::Relaxed) < index {
+                return Ok(None);
+            }
+            for noun in nouns.clone() {
+                if output(program, noun, verb)? == target {
+                    found.fetch_min(index, Ordering::Relaxed);
+                    return Ok(Some((noun, verb)));
+                }
+            }
+        }
+        Ok(None)
+    };
+
+    let results = scope(|s| {
+        let handles = (0..threads)
+            .map(|index| {
+                let scan = &scan;
+                s.spawn(move || match deadline {
+                    Some(deadline) => cancel::with_deadline(deadline, || scan(index)),
+                    None => scan(index),
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("search thread panicked"))
+            .collect::<Vec<_>>()
+    });
+    for result in results {
+        if let Some(inputs) = result? {
+            return Ok(Some(inputs));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::INPUTS;
+
+    #[test]
+    fn test_affine() {
+        let program = INPUTS[1].trim().parse::<Intcode>().unwrap();
+        let target = output(&program, 31, 46).unwrap();
+        let nouns = 0..100;
+        let verbs = 0..100;
+        assert!(solve_affine(&program, target, &nouns, &verbs)
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            find_inputs(&program, target, nouns, verbs),
+            Ok(Some((31, 46)))
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        // Multiplies the noun by the verb, which isn't affine
+        let program = "1102,0,0,0,99".parse::<Intcode>().unwrap();
+        assert_eq!(solve_affine(&program, 12, &(0..10), &(0..10)), Ok(None));
+        assert_eq!(find_inputs(&program, 12, 0..10, 0..10), Ok(Some((6, 2))));
+        assert_eq!(find_inputs(&program, 13, 0..10, 0..10), Ok(None));
+
+        let program = "1,0,0,0,3,0,99".parse::<Intcode>().unwrap();
+        assert_eq!(
+            find_inputs(&program, 5, 0..4, 0..4),
+            Err(SearchError::Stopped {
+                noun: 0,
+                verb: 0,
+                state: RunState::AwaitingInput
+            })
+        );
+    }
+}
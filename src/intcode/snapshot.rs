@@ -0,0 +1,165 @@
+/// Snapshots of the full state of Intcode programs
+///
+/// A snapshot is text with one field per line, so it can be saved between sessions, diffed and
+/// attached to bug reports:
+///
+/// ```text
+/// intcode snapshot 1
+/// pointer 2
+/// base 0
+/// finished false
+/// input 7,8
+/// output
+/// length 5
+/// memory 0 3,0,4,0,99
+/// ```
+///
+/// Memory is written as runs of values with their starting addresses. Traces, profiles and the
+/// memory limit belong to the session rather than the program, so they are not saved.
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::io::{BufRead, Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::str::FromStr;
+
+use super::memory::Memory;
+use super::{Code, Intcode};
+
+/// First line of every snapshot, naming the version of the format
+const HEADER: &str = "intcode snapshot 1";
+
+/// Line holding a field and its comma-separated values
+fn field<'a>(name: &str, values: impl Iterator<Item = &'a i64>) -> String {
+    let values = values.map(|v| v.to_string()).collect::<Vec<_>>();
+    format!("{} {}", name, values.join(","))
+        .trim_end()
+        .to_string()
+}
+
+fn invalid(line: usize, message: impl Display) -> IoError {
+    IoError::new(
+        ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+fn parse_values(line: usize, s: &str) -> IoResult<Vec<i64>> {
+    s.split(',')
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse::<i64>()
+                .map_err(|_| invalid(line, format!("expected a number, not {:?}", v)))
+        })
+        .collect()
+}
+
+fn parse<T: FromStr>(line: usize, s: &str) -> IoResult<T> {
+    s.parse::<T>()
+        .map_err(|_| invalid(line, format!("unexpected value {:?}", s)))
+}
+
+impl Intcode {
+    /// Writes a snapshot of the program's memory, pointer, relative base and queues
+    pub fn save(&self, mut writer: impl Write) -> IoResult<()> {
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "pointer {}", self.pointer)?;
+        writeln!(writer, "base {}", self.base)?;
+        writeln!(writer, "finished {}", self.finished)?;
+        writeln!(writer, "{}", field("input", self.queues.input.iter()))?;
+        writeln!(writer, "{}", field("output", self.queues.output.iter()))?;
+        writeln!(writer, "length {}", self.state.len())?;
+        for (address, values) in self.state.chunks() {
+            let name = format!("memory {}", address);
+            writeln!(writer, "{}", field(&name, values.iter()))?;
+        }
+        Ok(())
+    }
+
+    /// Reads a program back from a snapshot written by `save`
+    pub fn load(reader: impl BufRead) -> IoResult<Self> {
+        let mut program = Intcode::new(Vec::new());
+        let mut lines = reader.lines();
+        match lines.next() {
+            Some(Ok(header)) if header.trim_end() == HEADER => (),
+            Some(Err(err)) => return Err(err),
+            _ => return Err(invalid(1, format!("expected {:?}", HEADER))),
+        }
+        for (i, line) in lines.enumerate() {
+            let (number, line) = (i + 2, line?);
+            let line = line.trim_end();
+            let (field, value) = line.split_once(' ').unwrap_or((line, ""));
+            match field {
+                "" => (),
+                "pointer" => program.pointer = parse(number, value)?,
+                "base" => program.base = parse(number, value)?,
+                "finished" => program.finished = parse(number, value)?,
+                "input" => program.queues.input = VecDeque::from(parse_values(number, value)?),
+                "output" => program.queues.output = VecDeque::from(parse_values(number, value)?),
+                "length" => program.state.extend_to(parse(number, value)?),
+                "memory" => {
+                    let (address, values) = value.split_once(' ').unwrap_or((value, ""));
+                    let address = parse::<usize>(number, address)?;
+                    let values = parse_values(number, values)?;
+                    if address == 0 && program.state.image().is_empty() {
+                        let end = program.state.len();
+                        program.code = Code::new(values.len());
+                        program.state = Memory::new(values);
+                        program.state.extend_to(end);
+                    } else {
+                        for (offset, &value) in values.iter().enumerate() {
+                            program.state.force(address + offset, value);
+                        }
+                    }
+                }
+                _ => return Err(invalid(number, format!("unknown field {:?}", field))),
+            }
+        }
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::iter::{empty, once};
+
+    fn round_trip(program: &Intcode) -> Intcode {
+        let mut snapshot = Vec::new();
+        program.save(&mut snapshot).unwrap();
+        Intcode::load(&snapshot[..]).unwrap()
+    }
+
+    #[test]
+    fn test_save() {
+        let mut program = "3,0,4,0,99".parse::<Intcode>().unwrap();
+        program.run(empty()).unwrap();
+        program.push_input(7);
+        program.push_input(8);
+        let mut snapshot = Vec::new();
+        program.save(&mut snapshot).unwrap();
+        assert_eq!(
+            String::from_utf8(snapshot).unwrap(),
+            "intcode snapshot 1\npointer 0\nbase 0\nfinished false\ninput 7,8\noutput\n\
+             length 5\nmemory 0 3,0,4,0,99\n"
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let mut program = "109,3,21101,3,4,1000000000000,204,1000000000000,3,0,4,0,99"
+            .parse::<Intcode>()
+            .unwrap();
+        program.run(empty()).unwrap();
+        let mut restored = round_trip(&program);
+        assert_eq!(restored.to_string(), program.to_string());
+        assert_eq!(restored.value(1_000_000_000_003), 7);
+        assert_eq!(restored.base(), 3);
+        assert_eq!(restored.next(), Some(7));
+        restored.run(once(5)).unwrap();
+        assert!(restored.finished());
+        assert!(round_trip(&restored).finished());
+
+        let err = Intcode::load(&b"intcode snapshot 1\npointer x\n"[..]).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: unexpected value \"x\"");
+        assert!(Intcode::load(&b"1,2,3\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+/// Intcode workloads taken from the puzzle inputs, for comparing interpreters
+///
+/// Each workload runs the same program on a fixed input with either the reference interpreter or
+/// `Intcode`, returning everything output so the two can be checked against each other.
+use std::collections::{HashSet, VecDeque};
+
+use itertools::Itertools;
+
+use super::reference::Reference;
+use super::{Intcode, IntcodeError, Queues, RunState};
+use crate::INPUTS;
+
+/// Side of the area of the tractor beam probed by the day 19 workload
+const BEAM_SIZE: i64 = 50;
+
+/// Interpreter that can run a workload
+pub trait Machine: Clone {
+    fn load(memory: Vec<i64>) -> Self;
+
+    fn run_queues(&mut self, queues: &mut Queues) -> Result<RunState, IntcodeError>;
+}
+
+impl Machine for Intcode {
+    fn load(memory: Vec<i64>) -> Self {
+        Intcode::new(memory)
+    }
+
+    fn run_queues(&mut self, queues: &mut Queues) -> Result<RunState, IntcodeError> {
+        self.run_with(queues)
+    }
+}
+
+impl Machine for Reference {
+    fn load(memory: Vec<i64>) -> Self {
+        Reference::new(memory)
+    }
+
+    /// The reference interpreter panics rather than returning errors
+    fn run_queues(&mut self, queues: &mut Queues) -> Result<RunState, IntcodeError> {
+        let waiting = self.run(queues.input.drain(..));
+        queues.output.extend(self.drain());
+        Ok(if waiting {
+            RunState::AwaitingInput
+        } else {
+            RunState::Halted
+        })
+    }
+}
+
+type Runner = fn(&[i64]) -> Result<Vec<i64>, IntcodeError>;
+
+pub struct Workload {
+    pub name: &'static str,
+    pub day: usize,
+    pub reference: Runner,
+    pub decoded: Runner,
+}
+
+impl Workload {
+    /// Program from the puzzle input for the workload's day
+    pub fn memory(&self) -> Vec<i64> {
+        INPUTS[self.day - 1]
+            .trim()
+            .split(',')
+            .map(|v| v.parse().expect("puzzle input is not an Intcode program"))
+            .collect()
+    }
+}
+
+macro_rules! workload {
+    ($name:expr, $day:expr, $run:ident) => {
+        Workload {
+            name: $name,
+            day: $day,
+            reference: $run::<Reference>,
+            decoded: $run::<Intcode>,
+        }
+    };
+}
+
+pub const WORKLOADS: [Workload; 7] = [
+    workload!("d05 diagnostics", 5, diagnostics),
+    workload!("d07 amplifiers", 7, amplifiers),
+    workload!("d09 sensor boost", 9, sensor_boost),
+    workload!("d13 arcade screen", 13, screen),
+    workload!("d15 repair droid", 15, repair_droid),
+    workload!("d17 camera", 17, screen),
+    workload!("d19 tractor beam", 19, tractor_beam),
+];
+
+/// Runs a program once with some input, returning its output
+fn run<M: Machine>(program: &M, input: &[i64]) -> Result<Vec<i64>, IntcodeError> {
+    let mut queues = Queues {
+        input: input.iter().copied().collect(),
+        ..Queues::default()
+    };
+    program.clone().run_queues(&mut queues)?;
+    Ok(queues.output.into())
+}
+
+fn diagnostics<M: Machine>(memory: &[i64]) -> Result<Vec<i64>, IntcodeError> {
+    run(&M::load(memory.to_vec()), &[5])
+}
+
+fn sensor_boost<M: Machine>(memory: &[i64]) -> Result<Vec<i64>, IntcodeError> {
+    run(&M::load(memory.to_vec()), &[2])
+}
+
+fn screen<M: Machine>(memory: &[i64]) -> Result<Vec<i64>, IntcodeError> {
+    run(&M::load(memory.to_vec()), &[])
+}
+
+/// Highest signal from every order of phases, with the amplifiers in a feedback loop
+fn amplifiers<M: Machine>(memory: &[i64]) -> Result<Vec<i64>, IntcodeError> {
+    let program = M::load(memory.to_vec());
+    let mut best = i64::MIN;
+    for phases in (5..10).permutations(5) {
+        let mut amplifiers = vec![program.clone(); 5];
+        let mut queues = phases
+            .iter()
+            .map(|&p| Queues {
+                input: vec![p].into(),
+                ..Queues::default()
+            })
+            .collect::<Vec<Queues>>();
+        queues[0].input.push_back(0);
+        let mut signal = None;
+        'feedback: loop {
+            for i in 0..5 {
+                let state = amplifiers[i].run_queues(&mut queues[i])?;
+                let output = queues[i].output.drain(..).collect::<Vec<i64>>();
+                if i == 4 {
+                    signal = output.last().copied().or(signal);
+                    if state == RunState::Halted {
+                        break 'feedback;
+                    }
+                }
+                queues[(i + 1) % 5].input.extend(output);
+            }
+        }
+        best = best.max(signal.unwrap_or(i64::MIN));
+    }
+    Ok(vec![best])
+}
+
+/// Probes every point of a square, starting a fresh copy of the program each time
+fn tractor_beam<M: Machine>(memory: &[i64]) -> Result<Vec<i64>, IntcodeError> {
+    let program = M::load(memory.to_vec());
+    let mut output = Vec::new();
+    for y in 0..BEAM_SIZE {
+        for x in 0..BEAM_SIZE {
+            output.extend(run(&program, &[x, y])?);
+        }
+    }
+    Ok(output)
+}
+
+/// Explores the whole area around the repair droid breadth first, taking a copy of the droid for
+/// every move tried and returning the status reported after each
+fn repair_droid<M: Machine>(memory: &[i64]) -> Result<Vec<i64>, IntcodeError> {
+    let mut seen = HashSet::new();
+    seen.insert((0, 0));
+    let mut queue = VecDeque::new();
+    queue.push_back(((0, 0), M::load(memory.to_vec())));
+    let mut output = Vec::new();
+    while let Some(((x, y), droid)) = queue.pop_front() {
+        for &(command, dx, dy) in &[(1, 0, -1), (2, 0, 1), (3, -1, 0), (4, 1, 0)] {
+            let position = (x + dx, y + dy);
+            if !seen.insert(position) {
+                continue;
+            }
+            let mut droid = droid.clone();
+            let mut queues = Queues {
+                input: vec![command].into(),
+                ..Queues::default()
+            };
+            droid.run_queues(&mut queues)?;
+            let status = queues.output.pop_front().unwrap_or(0);
+            output.push(status);
+            if status != 0 {
+                queue.push_back((position, droid));
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_workloads() {
+        for workload in WORKLOADS.iter() {
+            let memory = workload.memory();
+            let expected = (workload.reference)(&memory).unwrap();
+            assert!(!expected.is_empty(), "{} has no output", workload.name);
+            assert_eq!((workload.decoded)(&memory).unwrap(), expected);
+        }
+    }
+}
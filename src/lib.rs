@@ -0,0 +1,104 @@
+//! Advent of Code 2019 solutions, with the Intcode computer and the registry of solvers used by
+//! the `aoc19` command line tool.
+//!
+//! ```
+//! let answer = aoc19::solve(1, 1, "12\n14").unwrap();
+//! assert_eq!(answer, "4");
+//! ```
+#[macro_use]
+extern crate lazy_static;
+
+use std::cell::Cell;
+use std::panic::{catch_unwind, set_hook, take_hook, AssertUnwindSafe};
+use std::sync::mpsc::channel;
+use std::thread::spawn;
+use std::time::{Duration, Instant};
+
+pub mod bench;
+pub mod cancel;
+pub mod check;
+pub mod days;
+pub mod intcode;
+pub mod output;
+pub mod select;
+
+use intcode::profile::with_profile;
+
+pub use days::{Context, Descriptor, SolveError, Solver, INPUTS, SOLVERS};
+
+thread_local! {
+    /// Whether the current thread is running a solver, whose panics are reported by `solve`
+    static SOLVING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Looks up the solver for a day and part, both counting from 1
+pub fn solver(day: usize, part: usize) -> Option<&'static Solver> {
+    SOLVERS
+        .iter()
+        .find(|s| s.day == day && s.part == part)
+        .map(|s| s.solver)
+}
+
+/// Solves a part of a day with the given input. Any panic within the solver is caught and
+/// returned as an error, so one broken day does not stop others from running.
+pub fn solve(day: usize, part: usize, data: &str) -> Result<String, SolveError> {
+    let solver = solver(day, part)
+        .ok_or_else(|| SolveError::new(format!("no solver for day {} part {}", day, part)))?;
+    let was_solving = SOLVING.with(|s| s.replace(true));
+    let result = catch_unwind(AssertUnwindSafe(|| solver(data)));
+    SOLVING.with(|s| s.set(was_solving));
+    result
+        .unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".to_string());
+            Err(SolveError::new(format!("solver panicked: {}", message)))
+        })
+        .map_err(|err| err.at(day, part))
+}
+
+/// Stops panic messages being printed for panics within solvers, as `solve` returns them as
+/// errors instead. Panics anywhere else are still printed as usual.
+pub fn quiet_solver_panics() {
+    let default = take_hook();
+    set_hook(Box::new(move |info| {
+        if !SOLVING.with(Cell::get) {
+            default(info);
+        }
+    }));
+}
+
+/// Solves a part of a day, giving up once the timeout has passed. The solver is run on its own
+/// thread with a deadline that long-running loops check, so most solvers stop soon after the
+/// timeout. Any solver that doesn't is left running in the background while this returns. Any
+/// Intcode profile being recorded carries over to the solver's thread.
+pub fn solve_with_timeout(
+    day: usize,
+    part: usize,
+    data: &str,
+    timeout: Duration,
+) -> Result<String, SolveError> {
+    let deadline = Instant::now() + timeout;
+    let data = data.to_string();
+    let (sender, receiver) = channel();
+    let profile = intcode::profile::current();
+    spawn(move || {
+        let result = with_profile(profile, || {
+            cancel::with_deadline(deadline, || {
+                let result = solve(day, part, &data);
+                match result {
+                    Err(_) if cancel::has_expired() => Err(SolveError::timed_out().at(day, part)),
+                    r => r,
+                }
+            })
+        });
+        sender.send(result).ok();
+    });
+    // Allow a little time past the deadline for the solver to notice and stop by itself
+    let grace = (timeout / 10).max(Duration::from_millis(10));
+    receiver
+        .recv_timeout(timeout + grace)
+        .unwrap_or_else(|_| Err(SolveError::timed_out().at(day, part)))
+}
@@ -1,15 +1,32 @@
 #[macro_use]
 extern crate clap;
-#[macro_use]
-extern crate lazy_static;
 
-use std::iter::once;
-use std::time::Instant;
+use std::fs::{read_to_string, File};
+use std::io::{stdin, stdout, BufReader, Result as IoResult};
+use std::path::Path;
+use std::process::exit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::thread::scope;
+use std::time::{Duration, Instant};
 
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, Error, ErrorKind, SubCommand};
 
-mod days;
-use days::SOLVERS;
+use aoc19::bench::{Bench, Stats};
+use aoc19::check::{diff, Answers, Verdict};
+use aoc19::days::{d25, parts_per_day};
+use aoc19::intcode::asm;
+use aoc19::intcode::debug::{repl, Debugger};
+use aoc19::intcode::disasm::listing;
+use aoc19::intcode::profile::{record, Profile};
+use aoc19::intcode::workload::WORKLOADS;
+use aoc19::intcode::Intcode;
+use aoc19::output::{Format, Record, Status};
+use aoc19::select::{group_by_day, parse_selection};
+use aoc19::{
+    quiet_solver_panics, solve, solve_with_timeout, Descriptor, SolveError, INPUTS, SOLVERS,
+};
+use itertools::Itertools;
 
 fn format_duration(nanoseconds: u128, threshold: u128) -> String {
     if nanoseconds < threshold {
@@ -30,75 +47,799 @@ fn format_duration(nanoseconds: u128, threshold: u128) -> String {
     }
 }
 
-fn solve_problems(days: Vec<usize>) {
-    let mut total_elapsed: u128 = 0;
-    for (i, day) in SOLVERS
-        .iter()
-        .enumerate()
-        .filter(|(i, _)| days.contains(&(i + 1)))
-    {
-        for (j, solver) in day.iter().enumerate() {
-            let now = Instant::now();
-            let result = solver();
-            let duration = now.elapsed().as_nanos();
-            let padding = if result.contains("\n") { "\n" } else { " " };
+/// Parses a duration such as `500ms`, `2s`, `1.5m` or `10`, which is taken to be in seconds
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value = value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid duration {:?}", s))?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        u => return Err(format!("unknown unit {:?}, expected ms, s or m", u)),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("invalid duration {:?}", s))
+}
+
+fn read_input(day: usize, file: Option<&str>, dir: Option<&str>) -> IoResult<String> {
+    let data = match (file, dir) {
+        (Some(f), _) => read_to_string(f)?,
+        (None, Some(d)) => {
+            let name = SOLVERS
+                .iter()
+                .find(|s| s.day == day)
+                .map_or_else(|| format!("d{:02}.txt", day), |s| s.input.clone());
+            let path = Path::new(d).join(name);
+            if path.exists() {
+                read_to_string(path)?
+            } else {
+                INPUTS[day - 1].to_string()
+            }
+        }
+        (None, None) => INPUTS[day - 1].to_string(),
+    };
+    Ok(data.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Result of running one part of a day, with the time taken for each timed run and the
+/// verdict if it was checked against a known answer
+struct Outcome {
+    day: usize,
+    part: usize,
+    result: Result<String, SolveError>,
+    samples: Vec<u128>,
+    verdict: Option<Verdict>,
+    profile: Option<Profile>,
+}
+
+impl Outcome {
+    fn duration(&self) -> u128 {
+        match self.samples.len() {
+            0 => 0,
+            n => self.samples.iter().sum::<u128>() / n as u128,
+        }
+    }
+
+    fn status(&self) -> Status {
+        match (&self.result, &self.verdict) {
+            (Err(err), _) if err.is_timed_out() => Status::TimedOut,
+            (Err(_), _) => Status::Failed,
+            (Ok(_), None) => Status::Ok,
+            (Ok(_), Some(Verdict::Pass)) => Status::Pass,
+            (Ok(_), Some(Verdict::Fail(_))) => Status::Mismatch,
+            (Ok(_), Some(Verdict::Missing)) => Status::Missing,
+        }
+    }
+
+    fn record(&self) -> Record<'_> {
+        Record {
+            day: self.day,
+            part: self.part,
+            status: self.status(),
+            answer: self.result.as_ref().ok().map(String::as_str),
+            error: self
+                .result
+                .as_ref()
+                .err()
+                .map(|err| err.chain().collect::<Vec<String>>().join(": ")),
+            duration: self.duration(),
+        }
+    }
+}
+
+/// Settings for how solvers are run and how their results are shown
+struct Options {
+    jobs: usize,
+    bench: Option<Bench>,
+    format: Format,
+    answers: Option<Answers>,
+    timeout: Option<Duration>,
+    profile: bool,
+}
+
+fn print_stats(stats: &Stats) {
+    println!(
+        "    min {}, median {}, mean {}, std dev {} ({} runs)",
+        format_duration(stats.min, 2000),
+        format_duration(stats.median, 2000),
+        format_duration(stats.mean, 2000),
+        format_duration(stats.std_dev, 2000),
+        stats.runs
+    );
+    if stats.low_outliers > 0 || stats.high_outliers > 0 {
+        println!(
+            "    outliers: {} low, {} high (max {})",
+            stats.low_outliers,
+            stats.high_outliers,
+            format_duration(stats.max, 2000)
+        );
+    }
+}
+
+fn print_outcome(outcome: &Outcome, bench: bool, format: Format) {
+    if let Some(record) = format.record(&outcome.record()) {
+        println!("{}", record);
+        return;
+    }
+    print_result(outcome, bench);
+    if let Some(profile) = &outcome.profile {
+        if profile.instructions > 0 {
+            for line in profile.to_string().lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+}
+
+fn print_result(outcome: &Outcome, bench: bool) {
+    let duration = format_duration(outcome.duration(), 2000);
+    match (&outcome.result, &outcome.verdict) {
+        (Ok(result), Some(Verdict::Fail(expected))) => {
+            println!(
+                "Day {}, part {}: FAIL ({})",
+                outcome.day, outcome.part, duration
+            );
+            for line in diff(expected, result) {
+                println!("    {}", line);
+            }
+        }
+        (Ok(result), verdict) => {
+            let label = match verdict {
+                Some(Verdict::Pass) => " PASS",
+                Some(Verdict::Missing) => " MISSING",
+                _ => "",
+            };
+            let padding = if result.contains('\n') { "\n" } else { " " };
+            if bench {
+                println!(
+                    "Day {}, part {}:{}{}{}",
+                    outcome.day, outcome.part, label, padding, result
+                );
+                if let Some(stats) = Stats::from_samples(&outcome.samples) {
+                    print_stats(&stats);
+                }
+            } else {
+                println!(
+                    "Day {}, part {}:{}{}{}{}({})",
+                    outcome.day, outcome.part, label, padding, result, padding, duration
+                );
+            }
+        }
+        (Err(err), _) if err.is_timed_out() => {
+            println!(
+                "Day {}, part {}: TIMED OUT ({})",
+                outcome.day, outcome.part, duration
+            );
+        }
+        (Err(err), _) => {
             println!(
-                "Day {}, part {}:{}{}{}({})",
-                i + 1,
-                j + 1,
-                padding,
-                result,
-                padding,
-                format_duration(duration, 2000)
+                "Day {}, part {}: FAILED ({})",
+                outcome.day, outcome.part, duration
             );
-            total_elapsed += duration;
+            let mut chain = err.chain();
+            if let Some(message) = chain.next() {
+                println!("    error: {}", message);
+            }
+            for cause in chain {
+                println!("    caused by: {}", cause);
+            }
+        }
+    }
+}
+
+/// Solves each part of the given days on a pool of worker threads, printing results in order.
+/// Each part is run repeatedly if benchmarking.
+fn solve_problems(days: Vec<(usize, Vec<usize>, String)>, options: &Options) -> Vec<Outcome> {
+    let Options {
+        jobs,
+        bench,
+        format,
+        ref answers,
+        timeout,
+        profile,
+    } = *options;
+    let run = |day, part, data| match timeout {
+        Some(t) => solve_with_timeout(day, part, data, t),
+        None => solve(day, part, data),
+    };
+    let tasks = days
+        .iter()
+        .flat_map(|(i, parts, data)| parts.iter().map(move |&j| (*i, j, data.as_str())))
+        .collect::<Vec<(usize, usize, &str)>>();
+
+    let next_task = AtomicUsize::new(0);
+    let (sender, receiver) = channel::<(usize, Outcome)>();
+    let now = Instant::now();
+    let mut total_elapsed: u128 = 0;
+    let mut outcomes = Vec::with_capacity(tasks.len());
+
+    if let Some(header) = format.header() {
+        println!("{}", header);
+    }
+    scope(|s| {
+        for _ in 0..jobs.min(tasks.len()) {
+            let sender = sender.clone();
+            let tasks = &tasks;
+            let next_task = &next_task;
+            let run = &run;
+            s.spawn(move || loop {
+                let index = next_task.fetch_add(1, Ordering::Relaxed);
+                let (day, part, data) = match tasks.get(index) {
+                    Some(&task) => task,
+                    None => break,
+                };
+                let timed = || match bench {
+                    Some(b) => b.run(|| run(day, part, data)),
+                    None => {
+                        let now = Instant::now();
+                        let result = run(day, part, data);
+                        (result, vec![now.elapsed().as_nanos()])
+                    }
+                };
+                let ((result, samples), profile) = if profile {
+                    let (timed, profile) = record(timed);
+                    (timed, Some(profile))
+                } else {
+                    (timed(), None)
+                };
+                let verdict = match (answers, &result) {
+                    (Some(a), Ok(answer)) => Some(a.check(day, part, answer)),
+                    _ => None,
+                };
+                let outcome = Outcome {
+                    day,
+                    part,
+                    result,
+                    samples,
+                    verdict,
+                    profile,
+                };
+                if sender.send((index, outcome)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        // Hold on to results finishing early until every part before them has been printed
+        let mut pending: Vec<Option<Outcome>> = tasks.iter().map(|_| None).collect();
+        for (index, outcome) in receiver {
+            pending[index] = Some(outcome);
+            while let Some(outcome) = pending.get_mut(outcomes.len()).and_then(Option::take) {
+                print_outcome(&outcome, bench.is_some(), format);
+                total_elapsed += outcome.duration();
+                outcomes.push(outcome);
+            }
+        }
+    });
+
+    if format == Format::Text {
+        println!(
+            "\nTotal elapsed: {}",
+            format_duration(now.elapsed().as_nanos(), 2000)
+        );
+        println!(
+            "Total solver time: {}",
+            format_duration(total_elapsed, 2000)
+        );
+    }
+    outcomes
+}
+
+/// Prints a table of solvers with their titles, tags and input files
+fn list_solvers(solvers: &[&Descriptor]) {
+    let title_width = solvers.iter().map(|s| s.title.len()).max().unwrap_or(0);
+    let tags = solvers
+        .iter()
+        .map(|s| s.tags.join(", "))
+        .collect::<Vec<String>>();
+    let tags_width = tags.iter().map(|t| t.len()).max().unwrap_or(0);
+    println!(
+        "{:>3}  {:>4}  {:<tw$}  {:<gw$}  Input",
+        "Day",
+        "Part",
+        "Title",
+        "Tags",
+        tw = title_width,
+        gw = tags_width
+    );
+    for (s, t) in solvers.iter().zip(tags.iter()) {
+        println!(
+            "{:>3}  {:>4}  {:<tw$}  {:<gw$}  {}",
+            s.day,
+            s.part,
+            s.title,
+            t,
+            s.input,
+            tw = title_width,
+            gw = tags_width
+        );
+    }
+}
+
+fn read_program(file: &str) -> Intcode {
+    let program = read_to_string(file)
+        .map_err(|err| err.to_string())
+        .and_then(|data| {
+            data.trim()
+                .parse::<Intcode>()
+                .map_err(|err| err.to_string())
+        });
+    program.unwrap_or_else(|err| {
+        eprintln!("Could not read Intcode program from {}: {}", file, err);
+        exit(1);
+    })
+}
+
+/// Times each workload on the reference interpreter and on `Intcode`, showing the speedup
+fn intcode_bench(bench: Bench) {
+    println!(
+        "{:<20} {:>10} {:>10} {:>8}",
+        "Workload", "Reference", "Decoded", "Speedup"
+    );
+    for workload in WORKLOADS.iter() {
+        let memory = workload.memory();
+        let time = |runner: fn(&[i64]) -> _| {
+            let (result, samples) = bench.run(|| runner(&memory));
+            if let Err(err) = result {
+                eprintln!("{} failed: {}", workload.name, err);
+                exit(1);
+            }
+            Stats::from_samples(&samples).map_or(0, |s| s.median)
+        };
+        let reference = time(workload.reference);
+        let decoded = time(workload.decoded);
+        println!(
+            "{:<20} {:>10} {:>10} {:>7.2}x",
+            workload.name,
+            format_duration(reference, 2000),
+            format_duration(decoded, 2000),
+            reference as f64 / decoded.max(1) as f64
+        );
+    }
+}
+
+/// Runs one of the `intcode` tools
+fn intcode_command(matches: &ArgMatches) {
+    if let Some(matches) = matches.subcommand_matches("disasm") {
+        let program = read_program(matches.value_of("FILE").unwrap());
+        print!("{}", listing(program.memory()));
+    } else if let Some(matches) = matches.subcommand_matches("debug") {
+        let program = read_program(matches.value_of("FILE").unwrap());
+        let mut debugger = Debugger::new(program);
+        if let Err(err) = repl(&mut debugger, stdin().lock(), stdout()) {
+            eprintln!("Debugger failed: {}", err);
+            exit(1);
+        }
+    } else if let Some(matches) = matches.subcommand_matches("asm") {
+        let file = matches.value_of("FILE").unwrap();
+        let source = read_to_string(file).unwrap_or_else(|err| {
+            eprintln!("Could not read {}: {}", file, err);
+            exit(1);
+        });
+        match asm::program(&source) {
+            Ok(program) => println!("{}", program),
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                exit(1);
+            }
         }
+    } else if let Some(matches) = matches.subcommand_matches("bench") {
+        intcode_bench(Bench {
+            warm_up: 1,
+            iterations: value_t_or_exit!(matches, "runs", usize),
+        });
+    }
+}
+
+/// Plays the day 25 game on the terminal, from the start or from a snapshot
+fn play_command(matches: &ArgMatches) {
+    let program = match matches.value_of("load") {
+        Some(file) => File::open(file)
+            .and_then(|f| Intcode::load(BufReader::new(f)))
+            .unwrap_or_else(|err| {
+                eprintln!("Could not load snapshot from {}: {}", file, err);
+                exit(1);
+            }),
+        None => match matches.value_of("input") {
+            Some(file) => read_program(file),
+            None => INPUTS[24].trim().parse::<Intcode>().unwrap_or_else(|err| {
+                eprintln!("Day 25 input is not an Intcode program: {}", err);
+                exit(1);
+            }),
+        },
+    };
+    if let Err(err) = d25::play(program, stdin().lock(), stdout()) {
+        eprintln!("Game failed: {}", err);
+        exit(1);
     }
-    println!("\nTotal elapsed: {}", format_duration(total_elapsed, 2000));
 }
 
 fn main() {
-    let num_days = SOLVERS.len();
+    let num_parts = parts_per_day();
+    let all_tags = SOLVERS
+        .iter()
+        .flat_map(|s| s.tags.iter().copied())
+        .unique()
+        .sorted()
+        .collect::<Vec<&str>>();
     let app = App::new(crate_name!())
         .version(crate_version!())
-        .author(crate_authors!("\n"))
+        .author(env!("CARGO_PKG_AUTHORS"))
         .about(crate_description!())
         .arg(
             Arg::with_name("last")
                 .long("last")
-                .help("Run the last day only."),
+                .help("Run the last selected day only."),
+        )
+        .arg(
+            Arg::with_name("input")
+                .long("input")
+                .value_name("FILE")
+                .takes_value(true)
+                .conflicts_with("input-dir")
+                .help("Read puzzle input from a file. Only one day can be selected."),
+        )
+        .arg(
+            Arg::with_name("input-dir")
+                .long("input-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .help(
+                    "Read puzzle inputs from files named d01.txt, d02.txt, etc. in a directory, \
+                     falling back to the embedded inputs for missing files.",
+                ),
+        )
+        .arg(
+            Arg::with_name("jobs")
+                .long("jobs")
+                .short("j")
+                .value_name("N")
+                .takes_value(true)
+                .default_value("1")
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(v) if v > 0 => Ok(()),
+                    _ => Err("Number of jobs must be a positive integer".to_string()),
+                })
+                .help("Run solvers on a pool of N threads."),
+        )
+        .arg(
+            Arg::with_name("bench")
+                .long("bench")
+                .value_name("ITERATIONS")
+                .takes_value(true)
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(v) if v > 0 => Ok(()),
+                    _ => Err("Number of iterations must be a positive integer".to_string()),
+                })
+                .help("Time each part over a number of runs and show statistics."),
+        )
+        .arg(
+            Arg::with_name("warm-up")
+                .long("warm-up")
+                .value_name("RUNS")
+                .takes_value(true)
+                .requires("bench")
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(_) => Ok(()),
+                    _ => Err("Number of warm-up runs must be a non-negative integer".to_string()),
+                })
+                .help("Untimed runs before benchmarking each part [default: 3]."),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .takes_value(true)
+                .possible_values(&Format::NAMES)
+                .default_value("text")
+                .help(
+                    "Output format. JSON writes one object per line; CSV and TSV start with a \
+                     header row. Line breaks in answers are escaped as \\n.",
+                ),
+        )
+        .arg(
+            Arg::with_name("check")
+                .long("check")
+                .value_name("FILE")
+                .takes_value(true)
+                .help(
+                    "Check answers against those in a TOML file, marking each part PASS, FAIL or \
+                     MISSING. Exits with 1 if any part fails, or 2 if any answers are missing.",
+                ),
+        )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .requires("check")
+                .help("Write answers to the file given by --check instead of checking them."),
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .value_name("DURATION")
+                .takes_value(true)
+                .validator(|v| parse_duration(&v).map(|_| ()))
+                .help(
+                    "Stop any part running longer than this, e.g. 500ms, 10s or 2m, and move on \
+                     to the next.",
+                ),
+        )
+        .arg(Arg::with_name("profile").long("profile").help(
+            "Count the Intcode instructions each part executes, by opcode and address. \
+             Shown with text output only.",
+        ))
+        .arg(
+            Arg::with_name("list")
+                .long("list")
+                .help("List the selected solvers with their titles, tags and input files."),
+        )
+        .arg(
+            Arg::with_name("tag")
+                .long("tag")
+                .value_name("TAG")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .possible_values(&all_tags)
+                .help("Only select solvers with this tag. Can be given more than once."),
         )
         .arg(
             Arg::with_name("DAY")
-                .help("Select days to calculate solutions for.")
+                .help(
+                    "Select days to calculate solutions for, as a day (7), a day and part (18:2), \
+                     a range of days (10-15) or a comma separated list of these.",
+                )
                 .multiple(true)
-                .validator(move |v| match v.parse::<usize>() {
-                    Ok(v) if v > 0 && v <= num_days => Ok(()),
-                    _ => Err(format!("Argument must be in range [1, {}]", num_days)),
+                .validator({
+                    let num_parts = num_parts.clone();
+                    move |v| parse_selection(&v, &num_parts).map(|_| ())
                 }),
+        )
+        .subcommand(
+            SubCommand::with_name("intcode")
+                .about("Tools for working with Intcode programs.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("disasm")
+                        .about(
+                            "Disassemble an Intcode program, marking anything not reached as \
+                             data.",
+                        )
+                        .arg(
+                            Arg::with_name("FILE")
+                                .required(true)
+                                .help("File containing the Intcode program."),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("asm")
+                        .about(
+                            "Assemble a program written with mnemonics and labels into \
+                             comma-separated Intcode.",
+                        )
+                        .arg(
+                            Arg::with_name("FILE")
+                                .required(true)
+                                .help("File containing the assembly source."),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("bench")
+                        .about(
+                            "Compare the speed of the Intcode interpreter with the simpler \
+                             reference interpreter on programs from the puzzle inputs.",
+                        )
+                        .arg(
+                            Arg::with_name("runs")
+                                .long("runs")
+                                .value_name("N")
+                                .takes_value(true)
+                                .default_value("10")
+                                .validator(|v| match v.parse::<usize>() {
+                                    Ok(v) if v > 0 => Ok(()),
+                                    _ => {
+                                        Err("Number of runs must be a positive integer".to_string())
+                                    }
+                                })
+                                .help("Timed runs of each workload on each interpreter."),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("debug")
+                        .about(
+                            "Step through an Intcode program with breakpoints and watchpoints. \
+                             Type help at the prompt for commands.",
+                        )
+                        .arg(
+                            Arg::with_name("FILE")
+                                .required(true)
+                                .help("File containing the Intcode program."),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("play")
+                .about(
+                    "Play the day 25 text adventure. Type save FILE or load FILE at the prompt \
+                     to checkpoint the game or resume it from a snapshot.",
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .long("input")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .help("Play the Intcode program in this file instead of the puzzle input."),
+                )
+                .arg(
+                    Arg::with_name("load")
+                        .long("load")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .conflicts_with("input")
+                        .help("Resume from a snapshot saved during an earlier game."),
+                ),
         );
 
     let matches = app.get_matches();
+    if let Some(matches) = matches.subcommand_matches("intcode") {
+        intcode_command(matches);
+        return;
+    }
+    if let Some(matches) = matches.subcommand_matches("play") {
+        play_command(matches);
+        return;
+    }
     let last = matches.is_present("last");
     let selected = matches.values_of("DAY");
 
-    let days = if selected.is_some() && !last {
-        selected
-            .unwrap()
-            .map(|x| x.parse::<usize>().unwrap())
-            .collect()
-    } else if selected.is_some() {
-        selected
-            .unwrap()
-            .rev()
-            .take(1)
-            .map(|x| x.parse::<usize>().unwrap())
-            .collect()
-    } else if !last {
-        (1..=num_days).collect()
+    let mut pairs: Vec<(usize, usize)> = match selected {
+        Some(s) => s
+            .flat_map(|x| parse_selection(x, &num_parts).unwrap())
+            .collect(),
+        None => SOLVERS.iter().map(|s| (s.day, s.part)).collect(),
+    };
+    if let Some(tags) = matches.values_of("tag") {
+        let tags = tags.collect::<Vec<&str>>();
+        pairs.retain(|&(d, p)| {
+            SOLVERS
+                .iter()
+                .any(|s| s.day == d && s.part == p && tags.iter().any(|t| s.has_tag(t)))
+        });
+    }
+    if pairs.is_empty() {
+        eprintln!("No solvers match the selected days and tags");
+        exit(1);
+    }
+    let mut days = group_by_day(&pairs);
+    if last {
+        days = days.pop().into_iter().collect();
+    }
+    // --last goes by the order days were selected, but they are always run in day order
+    days.sort_unstable_by_key(|&(day, _)| day);
+
+    if matches.is_present("list") {
+        let solvers = days
+            .iter()
+            .flat_map(|(d, parts)| {
+                parts
+                    .iter()
+                    .filter_map(move |p| SOLVERS.iter().find(|s| s.day == *d && s.part == *p))
+            })
+            .collect::<Vec<&Descriptor>>();
+        list_solvers(&solvers);
+        return;
+    }
+
+    let input = matches.value_of("input");
+    let input_dir = matches.value_of("input-dir");
+    if input.is_some() && days.len() != 1 {
+        Error::with_description(
+            "A single day must be selected when using --input.",
+            ErrorKind::ArgumentConflict,
+        )
+        .exit();
+    }
+
+    let inputs = days
+        .iter()
+        .map(|(d, p)| Ok((*d, p.clone(), read_input(*d, input, input_dir)?)))
+        .collect::<IoResult<Vec<(usize, Vec<usize>, String)>>>()
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to read input: {}", err);
+            exit(1);
+        });
+
+    // Panics in solvers are reported alongside other solver errors
+    quiet_solver_panics();
+    let jobs = value_t_or_exit!(matches, "jobs", usize);
+    let bench = if matches.is_present("bench") {
+        Some(Bench {
+            warm_up: matches
+                .value_of("warm-up")
+                .map_or(3, |w| w.parse::<usize>().unwrap()),
+            iterations: value_t_or_exit!(matches, "bench", usize),
+        })
     } else {
-        once(num_days).collect()
+        None
     };
+    let answers_path = matches.value_of("check").map(Path::new);
+    let record = matches.is_present("record");
+    let answers = match answers_path {
+        Some(path) if record => Answers::load_or_default(path),
+        Some(path) => Answers::load(path),
+        None => Ok(Answers::default()),
+    }
+    .unwrap_or_else(|err| {
+        eprintln!("Failed to read answers: {}", err);
+        exit(1);
+    });
+    let options = Options {
+        jobs,
+        bench,
+        format: value_t_or_exit!(matches, "format", Format),
+        answers: if answers_path.is_some() && !record {
+            Some(answers.clone())
+        } else {
+            None
+        },
+        timeout: matches
+            .value_of("timeout")
+            .map(|t| parse_duration(t).unwrap()),
+        profile: matches.is_present("profile"),
+    };
+    let outcomes = solve_problems(inputs, &options);
 
-    solve_problems(days);
+    if let (Some(path), true) = (answers_path, record) {
+        let mut answers = answers;
+        for o in outcomes.iter() {
+            if let Ok(answer) = &o.result {
+                answers.insert(o.day, o.part, answer);
+            }
+        }
+        answers.save(path).unwrap_or_else(|err| {
+            eprintln!("Failed to write answers: {}", err);
+            exit(1);
+        });
+        eprintln!("Recorded {} answers to {}", answers.len(), path.display());
+    }
+
+    let describe = |status: Status| {
+        outcomes
+            .iter()
+            .filter(|o| o.status() == status)
+            .map(|o| format!("day {} part {}", o.day, o.part))
+            .collect::<Vec<String>>()
+    };
+    let failed = outcomes
+        .iter()
+        .filter_map(|o| o.result.as_ref().err())
+        .filter(|err| !err.is_timed_out())
+        .filter_map(|err| Some(format!("day {} part {}", err.day()?, err.part()?)))
+        .collect::<Vec<String>>();
+    let timed_out = describe(Status::TimedOut);
+    let mismatched = describe(Status::Mismatch);
+    let missing = describe(Status::Missing);
+    if !failed.is_empty() {
+        eprintln!("Failed: {}", failed.join(", "));
+    }
+    if !timed_out.is_empty() {
+        eprintln!("Timed out: {}", timed_out.join(", "));
+    }
+    if !mismatched.is_empty() {
+        eprintln!("Wrong answers: {}", mismatched.join(", "));
+    }
+    if !missing.is_empty() {
+        eprintln!("Missing answers: {}", missing.join(", "));
+    }
+    if !failed.is_empty() || !timed_out.is_empty() || !mismatched.is_empty() {
+        exit(1);
+    } else if !missing.is_empty() {
+        exit(2);
+    }
 }
@@ -0,0 +1,245 @@
+/// Machine-readable formats for solver results
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl Format {
+    pub const NAMES: [&'static str; 4] = ["text", "json", "csv", "tsv"];
+
+    /// Line written before any records, if the format has one
+    pub fn header(&self) -> Option<String> {
+        match self {
+            Format::Text | Format::Json => None,
+            Format::Csv => Some(Record::FIELDS.join(",")),
+            Format::Tsv => Some(Record::FIELDS.join("\t")),
+        }
+    }
+
+    /// Formats a single record as one line, or `None` for the text format
+    pub fn record(&self, record: &Record) -> Option<String> {
+        let values = record.values();
+        match self {
+            Format::Text => None,
+            Format::Json => {
+                let fields = Record::FIELDS
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(name, value)| format!("{}:{}", escape_json(name), value.to_json()))
+                    .collect::<Vec<String>>();
+                Some(format!("{{{}}}", fields.join(",")))
+            }
+            Format::Csv => Some(
+                values
+                    .iter()
+                    .map(|v| escape_csv(&v.to_string()))
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ),
+            Format::Tsv => Some(
+                values
+                    .iter()
+                    .map(|v| escape_tsv(&v.to_string()))
+                    .collect::<Vec<String>>()
+                    .join("\t"),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFormatError(String);
+
+impl Display for ParseFormatError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "unknown format {:?}, expected one of {}",
+            self.0,
+            Format::NAMES.join(", ")
+        )
+    }
+}
+
+impl FromStr for Format {
+    type Err = ParseFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            _ => Err(ParseFormatError(s.to_string())),
+        }
+    }
+}
+
+/// Whether a part was solved, and if checked against a known answer whether it matched
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Failed,
+    TimedOut,
+    Pass,
+    Mismatch,
+    Missing,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Failed => "failed",
+            Status::TimedOut => "timed_out",
+            Status::Pass => "pass",
+            Status::Mismatch => "mismatch",
+            Status::Missing => "missing",
+        }
+    }
+}
+
+/// Outcome of one part of a day, flattened for output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub day: usize,
+    pub part: usize,
+    pub status: Status,
+    pub answer: Option<&'a str>,
+    pub error: Option<String>,
+    pub duration: u128,
+}
+
+enum Value<'a> {
+    Number(u128),
+    Text(&'a str),
+    Null,
+}
+
+impl Value<'_> {
+    fn to_json(&self) -> String {
+        match self {
+            Value::Number(n) => n.to_string(),
+            Value::Text(s) => escape_json(s),
+            Value::Null => "null".to_string(),
+        }
+    }
+}
+
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Text(s) => write!(f, "{}", s),
+            Value::Null => Ok(()),
+        }
+    }
+}
+
+impl Record<'_> {
+    const FIELDS: [&'static str; 6] = ["day", "part", "status", "answer", "duration_ns", "error"];
+
+    fn values(&self) -> [Value<'_>; 6] {
+        [
+            Value::Number(self.day as u128),
+            Value::Number(self.part as u128),
+            Value::Text(self.status.as_str()),
+            self.answer.map_or(Value::Null, Value::Text),
+            Value::Number(self.duration),
+            self.error.as_deref().map_or(Value::Null, Value::Text),
+        ]
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Escapes backslashes and line breaks so every record stays on one line
+fn escape_lines(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn escape_csv(s: &str) -> String {
+    let escaped = escape_lines(s);
+    if escaped.contains([',', '"']) {
+        format!("\"{}\"", escaped.replace('"', "\"\""))
+    } else {
+        escaped
+    }
+}
+
+fn escape_tsv(s: &str) -> String {
+    escape_lines(s).replace('\t', "\\t")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_records() {
+        let record = Record {
+            day: 8,
+            part: 2,
+            status: Status::Ok,
+            answer: Some("# \"a\",\tb\n #"),
+            error: None,
+            duration: 1500,
+        };
+        assert_eq!(
+            Format::Json.record(&record).unwrap(),
+            r##"{"day":8,"part":2,"status":"ok","answer":"# \"a\",\tb\n #","duration_ns":1500,"error":null}"##
+        );
+        assert_eq!(
+            Format::Csv.record(&record).unwrap(),
+            "8,2,ok,\"# \"\"a\"\",\tb\\n #\",1500,"
+        );
+        assert_eq!(
+            Format::Tsv.record(&record).unwrap(),
+            "8\t2\tok\t# \"a\",\\tb\\n #\t1500\t"
+        );
+    }
+
+    #[test]
+    fn test_failed_record() {
+        let record = Record {
+            day: 3,
+            part: 1,
+            status: Status::Failed,
+            answer: None,
+            error: Some("wires do not intersect".to_string()),
+            duration: 20,
+        };
+        assert_eq!(
+            Format::Csv.record(&record).unwrap(),
+            "3,1,failed,,20,wires do not intersect"
+        );
+        assert_eq!(
+            Format::Csv.header().unwrap(),
+            "day,part,status,answer,duration_ns,error"
+        );
+    }
+}
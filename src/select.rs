@@ -0,0 +1,108 @@
+/// Parses a selection such as `7`, `18:2`, `10-15` or a comma separated list of these into day
+/// and part pairs, checking each against the number of parts each day has
+pub fn parse_selection(s: &str, parts: &[usize]) -> Result<Vec<(usize, usize)>, String> {
+    let mut selected = Vec::new();
+    for item in s.split(',').map(str::trim) {
+        if item.is_empty() {
+            return Err(format!("empty item in selection {:?}", s));
+        }
+        if let Some((start, end)) = item.split_once('-') {
+            let start = parse_day(start, parts)?;
+            let end = parse_day(end, parts)?;
+            if start > end {
+                return Err(format!("range {:?} must not run backwards", item));
+            }
+            for day in start..=end {
+                selected.extend((1..=parts[day - 1]).map(|p| (day, p)));
+            }
+        } else if let Some((day, part)) = item.split_once(':') {
+            let day = parse_day(day, parts)?;
+            let part = part
+                .parse::<usize>()
+                .map_err(|_| format!("invalid part {:?}", part))?;
+            if part == 0 || part > parts[day - 1] {
+                return Err(match parts[day - 1] {
+                    1 => format!("day {} has no part {}, only part 1", day, part),
+                    n => format!("day {} has no part {}, only parts 1-{}", day, part, n),
+                });
+            }
+            selected.push((day, part));
+        } else {
+            let day = parse_day(item, parts)?;
+            selected.extend((1..=parts[day - 1]).map(|p| (day, p)));
+        }
+    }
+    Ok(selected)
+}
+
+fn parse_day(s: &str, parts: &[usize]) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(d) if d > 0 && d <= parts.len() => Ok(d),
+        _ => Err(format!("day {:?} must be in range [1, {}]", s, parts.len())),
+    }
+}
+
+/// Groups day and part pairs by day, keeping days in the order they were first selected and
+/// sorting their parts
+pub fn group_by_day(selected: &[(usize, usize)]) -> Vec<(usize, Vec<usize>)> {
+    let mut days: Vec<(usize, Vec<usize>)> = Vec::new();
+    for &(day, part) in selected.iter() {
+        match days.iter_mut().find(|(d, _)| *d == day) {
+            Some((_, p)) if p.contains(&part) => (),
+            Some((_, p)) => p.push(part),
+            None => days.push((day, vec![part])),
+        }
+    }
+    for (_, p) in days.iter_mut() {
+        p.sort_unstable();
+    }
+    days
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PARTS: [usize; 25] = [
+        2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1,
+    ];
+
+    #[test]
+    fn test_selection() {
+        assert_eq!(parse_selection("7", &PARTS), Ok(vec![(7, 1), (7, 2)]));
+        assert_eq!(parse_selection("18:2", &PARTS), Ok(vec![(18, 2)]));
+        assert_eq!(
+            parse_selection("23-25", &PARTS),
+            Ok(vec![(23, 1), (23, 2), (24, 1), (24, 2), (25, 1)])
+        );
+        assert_eq!(
+            parse_selection("1:2, 3,2:1", &PARTS),
+            Ok(vec![(1, 2), (3, 1), (3, 2), (2, 1)])
+        );
+    }
+
+    #[test]
+    fn test_invalid_selection() {
+        assert_eq!(
+            parse_selection("25:2", &PARTS),
+            Err("day 25 has no part 2, only part 1".to_string())
+        );
+        assert_eq!(
+            parse_selection("3:0", &PARTS),
+            Err("day 3 has no part 0, only parts 1-2".to_string())
+        );
+        assert!(parse_selection("0", &PARTS).is_err());
+        assert!(parse_selection("26", &PARTS).is_err());
+        assert!(parse_selection("15-10", &PARTS).is_err());
+        assert!(parse_selection("1,,2", &PARTS).is_err());
+        assert!(parse_selection("a:1", &PARTS).is_err());
+    }
+
+    #[test]
+    fn test_group_by_day() {
+        assert_eq!(
+            group_by_day(&[(3, 2), (1, 1), (3, 1), (1, 1)]),
+            vec![(3, vec![1, 2]), (1, vec![1])]
+        );
+    }
+}